@@ -0,0 +1,52 @@
+use rand::seq::IndexedRandom;
+
+use crate::{Osero, Pattern, bitboard};
+
+pub const POSITION_SCORE: [i32; 64] = [
+    100, -20, 10, 5, 5, 10, -20, 100, -20, -50, -2, -2, -2, -2, -50, -20, 10, -2, 5, 1, 1, 5, -2,
+    10, 5, -2, 1, 0, 0, 1, -2, 5, 5, -2, 1, 0, 0, 1, -2, 5, 10, -2, 5, 1, 1, 5, -2, 10, -20, -50,
+    -2, -2, -2, -2, -50, -20, 100, -20, 10, 5, 5, 10, -20, 100,
+];
+
+impl Osero {
+    pub fn count_all_flips(&self, with: Pattern, at: (usize, usize)) -> usize {
+        if at.0 >= 8 || at.1 >= 8 {
+            return 0;
+        }
+        let (player, opponent) = self.sides(with);
+        bitboard::flips(player, opponent, bitboard::bit(at)).count_ones() as usize
+    }
+
+    pub fn best_move(&self, with: Pattern, level: usize) -> Option<(usize, usize)> {
+        if level == 1 {
+            self.level1(with)
+        } else {
+            self.search(with, level).best_move
+        }
+    }
+
+    pub fn level1(&self, with: Pattern) -> Option<(usize, usize)> {
+        let mut best_score = usize::MIN;
+        let mut best_moves = vec![];
+
+        for y in 0..8 {
+            for x in 0..8 {
+                let flips = self.count_all_flips(with, (x, y));
+                if flips == 0 {
+                    continue;
+                }
+                let total_score = flips;
+
+                if total_score > best_score {
+                    best_score = total_score;
+                    best_moves = vec![(x, y)];
+                } else if total_score == best_score {
+                    best_moves.push((x, y));
+                }
+            }
+        }
+
+        let mut rng = rand::rng();
+        best_moves.choose(&mut rng).copied()
+    }
+}
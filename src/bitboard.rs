@@ -0,0 +1,143 @@
+// ビット i はマス (x, y) = (i % 8, i / 8) に対応する
+
+const NOT_A_FILE: u64 = 0xfefe_fefe_fefe_fefe; // x == 0 の列を除く
+const NOT_H_FILE: u64 = 0x7f7f_7f7f_7f7f_7f7f; // x == 7 の列を除く
+
+// 8方向へのシフト。盤の端を越えたビットは落とす。2つずつ組で同じ線の逆向きになる
+pub(crate) const DIRECTIONS: [fn(u64) -> u64; 8] = [
+    |b| (b << 1) & NOT_A_FILE, // 右
+    |b| (b >> 1) & NOT_H_FILE, // 左
+    |b| b << 8,                // 下
+    |b| b >> 8,                // 上
+    |b| (b << 9) & NOT_A_FILE, // 右下
+    |b| (b >> 9) & NOT_H_FILE, // 左上
+    |b| (b << 7) & NOT_H_FILE, // 左下
+    |b| (b >> 7) & NOT_A_FILE, // 右上
+];
+
+pub(crate) fn bit(at: (usize, usize)) -> u64 {
+    1 << (at.1 * 8 + at.0)
+}
+
+pub(crate) fn at_of(index: u32) -> (usize, usize) {
+    (index as usize % 8, index as usize / 8)
+}
+
+/// 8方向の隣のマスをすべて返す
+pub(crate) fn neighbours(bits: u64) -> u64 {
+    DIRECTIONS.iter().fold(0, |acc, shift| acc | shift(bits))
+}
+
+/// `player` が置けるマスをすべて返す
+pub(crate) fn legal_moves(player: u64, opponent: u64) -> u64 {
+    let empty = !(player | opponent);
+    let mut moves = 0;
+    for shift in DIRECTIONS {
+        let mut x = shift(player) & opponent;
+        for _ in 0..5 {
+            x |= shift(x) & opponent;
+        }
+        moves |= shift(x) & empty;
+    }
+    moves
+}
+
+/// `put` の位置に置いたときにひっくり返る石を返す
+pub(crate) fn flips(player: u64, opponent: u64, put: u64) -> u64 {
+    if (player | opponent) & put != 0 {
+        return 0;
+    }
+    let mut result = 0;
+    for shift in DIRECTIONS {
+        let mut line = 0;
+        let mut x = shift(put);
+        while x & opponent != 0 {
+            line |= x;
+            x = shift(x);
+        }
+        if x & player != 0 {
+            result |= line;
+        }
+    }
+    result
+}
+
+/// 立っているビットを小さい順に取り出すイテレータ
+pub(crate) fn squares(mut bits: u64) -> impl Iterator<Item = (usize, usize)> {
+    std::iter::from_fn(move || {
+        if bits == 0 {
+            return None;
+        }
+        let index = bits.trailing_zeros();
+        bits &= bits - 1;
+        Some(at_of(index))
+    })
+}
+
+/// 盤の8通りの回転・反転。`symmetry` の各ビットで 1 = 上下反転、2 = 左右反転、4 = 対角線で反転
+pub(crate) fn transform(mut bits: u64, symmetry: usize) -> u64 {
+    if symmetry & 4 != 0 {
+        // (x, y) と (y, x) を入れ替える
+        let mut t = 0x0f0f_0f0f_0000_0000 & (bits ^ (bits << 28));
+        bits ^= t ^ (t >> 28);
+        t = 0x3333_0000_3333_0000 & (bits ^ (bits << 14));
+        bits ^= t ^ (t >> 14);
+        t = 0x5500_5500_5500_5500 & (bits ^ (bits << 7));
+        bits ^= t ^ (t >> 7);
+    }
+    if symmetry & 2 != 0 {
+        bits = ((bits >> 1) & 0x5555_5555_5555_5555) | ((bits & 0x5555_5555_5555_5555) << 1);
+        bits = ((bits >> 2) & 0x3333_3333_3333_3333) | ((bits & 0x3333_3333_3333_3333) << 2);
+        bits = ((bits >> 4) & 0x0f0f_0f0f_0f0f_0f0f) | ((bits & 0x0f0f_0f0f_0f0f_0f0f) << 4);
+    }
+    if symmetry & 1 != 0 {
+        bits = bits.swap_bytes();
+    }
+    bits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Osero, Pattern};
+
+    // 葉の数を数える。パスも1手と数え、両者とも置けなければそこで葉にする
+    fn perft(player: u64, opponent: u64, depth: usize, passed: bool) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let moves = legal_moves(player, opponent);
+        if moves == 0 {
+            if passed {
+                return 1;
+            }
+            return perft(opponent, player, depth - 1, true);
+        }
+        squares(moves)
+            .map(|at| {
+                let put = bit(at);
+                let flipped = flips(player, opponent, put);
+                perft(
+                    opponent & !flipped,
+                    player | put | flipped,
+                    depth - 1,
+                    false,
+                )
+            })
+            .sum()
+    }
+
+    #[test]
+    fn perft_from_start() {
+        let (player, opponent) = Osero::default().sides(Pattern::Black);
+        let expected = [4, 12, 56, 244, 1396, 8200, 55092, 390216];
+        for (depth, &count) in expected.iter().enumerate() {
+            assert_eq!(
+                perft(player, opponent, depth + 1, false),
+                count,
+                "depth {}",
+                depth + 1
+            );
+        }
+    }
+}
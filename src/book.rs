@@ -0,0 +1,517 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::bitboard::{self, bit, transform};
+use crate::endgame::final_difference;
+use crate::train::play_games;
+use crate::{Engine, Move, Osero, Pattern, format_moves, parse_moves};
+
+/// 定石の1手と、その手を打った対局の成績
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BookMove {
+    pub at: (usize, usize),
+    /// この手を打った対局の数
+    pub games: u32,
+    /// この手を打った側から見た最終的な石差の合計
+    pub score: i64,
+}
+
+impl BookMove {
+    /// 1局あたりの石差。まだ打たれたことがなければ 0
+    pub fn average(&self) -> f64 {
+        if self.games == 0 {
+            0.0
+        } else {
+            self.score as f64 / self.games as f64
+        }
+    }
+}
+
+// 回転・反転をそろえた局面の (手番側, 相手) の石
+type Key = (u64, u64);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Edge {
+    // 回転・反転をそろえたあとのマスの番号
+    square: u32,
+    games: u32,
+    score: i64,
+}
+
+/// 名前つきの定石と、自己対局で覚えた手の成績を持つ定石集。
+/// 局面は回転・反転で同じになるものをまとめて引く
+#[derive(Clone, Default, Debug)]
+pub struct OpeningBook {
+    moves: HashMap<Key, Vec<Edge>>,
+    names: HashMap<Key, String>,
+    randomness: u32,
+}
+
+impl OpeningBook {
+    /// 自己対局で一度に打つ対局の数。この数ごとに覚えた手を次の対局に使う
+    const LEARNING_ROUND: usize = 16;
+
+    pub fn new() -> Self {
+        OpeningBook::default()
+    }
+
+    /// 1局あたりの石差が最善の手から `discs` 枚以内の手をすべて候補にして、その中から選ぶ。
+    /// 0 なら最善の手だけから選ぶ
+    pub fn with_randomness(mut self, discs: u32) -> Self {
+        self.randomness = discs;
+        self
+    }
+
+    pub fn randomness(&self) -> u32 {
+        self.randomness
+    }
+
+    /// 定石に入っている局面の数
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    /// `with` の手番の局面での定石の手をすべて返す
+    pub fn moves(&self, osero: &Osero, with: Pattern) -> Vec<BookMove> {
+        let (key, symmetry) = canonical(osero, with);
+        let Some(edges) = self.moves.get(&key) else {
+            return vec![];
+        };
+        // 合法手のうち、そろえたときに定石のマスに重なるものが実際の盤での手
+        bitboard::squares(osero.moveable_bits(with))
+            .filter_map(|at| {
+                let square = transform(bit(at), symmetry).trailing_zeros();
+                let edge = edges.iter().find(|e| e.square == square)?;
+                Some(BookMove {
+                    at,
+                    games: edge.games,
+                    score: edge.score,
+                })
+            })
+            .collect()
+    }
+
+    /// 定石の手から1つ選ぶ。定石から外れていれば None
+    pub fn choose(
+        &self,
+        osero: &Osero,
+        with: Pattern,
+        rng: &mut impl Rng,
+    ) -> Option<(usize, usize)> {
+        let moves = self.moves(osero, with);
+        let best = moves
+            .iter()
+            .map(|m| m.average())
+            .max_by(|a, b| a.total_cmp(b))?;
+        let candidates: Vec<(usize, usize)> = moves
+            .iter()
+            .filter(|m| m.average() >= best - self.randomness as f64)
+            .map(|m| m.at)
+            .collect();
+        candidates.choose(rng).copied()
+    }
+
+    /// この局面につけられた定石の名前
+    pub fn name(&self, osero: &Osero, with: Pattern) -> Option<&str> {
+        self.names
+            .get(&canonical(osero, with).0)
+            .map(String::as_str)
+    }
+
+    /// 初期局面から `moves` を打った1局を、最初の `depth` 手まで覚える。
+    /// 置けない手があれば何も覚えずに false を返す
+    pub fn learn(&mut self, moves: &[(usize, usize)], depth: usize) -> bool {
+        let Some(line) = replay(moves) else {
+            return false;
+        };
+        let Some(&(mut last, with, at)) = line.last() else {
+            return true;
+        };
+        last.put(with, at);
+        let (black, white) = (last.bitboard(Pattern::Black), last.bitboard(Pattern::White));
+        for &(osero, with, at) in line.iter().take(depth) {
+            let result = match with {
+                Pattern::White => final_difference(white, black),
+                _ => final_difference(black, white),
+            };
+            let edge = self.edge(&osero, with, at);
+            edge.games += 1;
+            edge.score += result as i64;
+        }
+        true
+    }
+
+    /// `engine` どうしで `games` 局打ち、最初の `depth` 手を覚える。定石にある局面では定石の手を打つ。
+    /// 同じ `seed` なら同じ対局になる。`report` には打ち終えた対局の数を渡す
+    pub fn learn_by_self_play(
+        &mut self,
+        engine: impl Fn() -> Engine + Sync,
+        games: usize,
+        level: usize,
+        depth: usize,
+        seed: u64,
+        mut report: impl FnMut(usize),
+    ) {
+        let mut done = 0;
+        while done < games {
+            let round = Self::LEARNING_ROUND.min(games - done);
+            let book = &*self;
+            let records = play_games(&engine, done..done + round, seed, |engine, seed| {
+                book.play_game(engine, level, depth, seed)
+            });
+            for record in records {
+                self.learn(&record, depth);
+            }
+            done += round;
+            report(done);
+        }
+    }
+
+    fn play_game(
+        &self,
+        engine: &Engine,
+        level: usize,
+        depth: usize,
+        seed: u64,
+    ) -> Vec<(usize, usize)> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut osero = Osero::default();
+        let mut with = Pattern::Black;
+        let mut record = vec![];
+        while !osero.is_finished() {
+            if osero.is_moveable_with(with) {
+                let book_move = if record.len() < depth {
+                    self.choose(&osero, with, &mut rng)
+                } else {
+                    None
+                };
+                let at = book_move.or_else(|| engine.search(&osero, with, level).best_move);
+                if let Some(at) = at {
+                    osero.put(with, at);
+                    record.push(at);
+                }
+            }
+            with = with.fliped();
+        }
+        record
+    }
+
+    fn edge(&mut self, osero: &Osero, with: Pattern, at: (usize, usize)) -> &mut Edge {
+        let (key, symmetry) = canonical(osero, with);
+        let square = transform(bit(at), symmetry).trailing_zeros();
+        let edges = self.moves.entry(key).or_default();
+        let index = match edges.iter().position(|e| e.square == square) {
+            Some(index) => index,
+            None => {
+                edges.push(Edge {
+                    square,
+                    games: 0,
+                    score: 0,
+                });
+                edges.len() - 1
+            }
+        };
+        &mut edges[index]
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut book = OpeningBook::new();
+        book.read_from(BufReader::new(File::open(path)?))?;
+        Ok(book)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    /// 1行に1つ「手順 [対局数:石差の合計] [名前]」の形で読む。手順は `f5d6c3` のように続けて書き、
+    /// パスは書かなくてよい。`#` から行末まではコメント
+    pub fn read_from(&mut self, reader: impl BufRead) -> io::Result<()> {
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = |message: &str| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}行目: {}", i + 1, message),
+                )
+            };
+
+            let mut words = line.split_whitespace();
+            let sequence = words.next().unwrap_or_default();
+            let moves: Vec<(usize, usize)> = parse_moves(sequence)
+                .map_err(|e| invalid(&e.to_string()))?
+                .iter()
+                .filter_map(Move::at)
+                .collect();
+            let positions = replay(&moves).ok_or_else(|| invalid("置けない手があります"))?;
+            let mut rest: Vec<&str> = words.collect();
+            let stats = match rest.first().and_then(|word| parse_stats(word)) {
+                Some(stats) => {
+                    rest.remove(0);
+                    Some(stats)
+                }
+                None => None,
+            };
+
+            let Some((&(last, with, at), prefix)) = positions.split_last() else {
+                continue;
+            };
+            for &(osero, with, at) in prefix {
+                self.edge(&osero, with, at);
+            }
+            let edge = self.edge(&last, with, at);
+            if let Some((games, score)) = stats {
+                edge.games = games;
+                edge.score = score;
+            }
+            if !rest.is_empty() {
+                let mut osero = last;
+                osero.put(with, at);
+                let next = next_turn(&osero, with);
+                self.names.insert(canonical(&osero, next).0, rest.join(" "));
+            }
+        }
+        Ok(())
+    }
+
+    /// 初期局面から定石をたどり、成績か名前のある手と、手順の終わりを1行ずつ書き出す
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        let mut visited = HashSet::new();
+        let mut stack = vec![(Osero::default(), Pattern::Black, vec![])];
+        visited.insert(canonical(&Osero::default(), Pattern::Black).0);
+        while let Some((osero, with, line)) = stack.pop() {
+            let mut moves = self.moves(&osero, with);
+            moves.sort_by_key(|m| std::cmp::Reverse(m.at.1 * 8 + m.at.0));
+            for book_move in moves {
+                let mut next = osero;
+                next.put(with, book_move.at);
+                let next_with = next_turn(&next, with);
+                let mut line = line.clone();
+                line.push(Move::from(book_move.at));
+
+                let key = canonical(&next, next_with).0;
+                let name = self.names.get(&key);
+                let leaf = self.moves(&next, next_with).is_empty();
+                let seen = !visited.insert(key);
+                if book_move.games > 0 || name.is_some() || leaf || seen {
+                    write!(writer, "{}", format_moves(&line))?;
+                    if book_move.games > 0 {
+                        write!(writer, " {}:{:+}", book_move.games, book_move.score)?;
+                    }
+                    if let Some(name) = name {
+                        write!(writer, " {}", name)?;
+                    }
+                    writeln!(writer)?;
+                }
+                if !seen {
+                    stack.push((next, next_with, line));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// 8通りの回転・反転のうち、(手番側, 相手) が一番小さくなるものとその番号
+fn canonical(osero: &Osero, with: Pattern) -> (Key, usize) {
+    let (player, opponent) = osero.sides(with);
+    (0..8)
+        .map(|symmetry| {
+            (
+                (transform(player, symmetry), transform(opponent, symmetry)),
+                symmetry,
+            )
+        })
+        .min()
+        .expect("8通りある")
+}
+
+// 相手が置けなければパスして同じ手番が続く
+fn next_turn(osero: &Osero, with: Pattern) -> Pattern {
+    if !osero.is_moveable_with(with.fliped()) && osero.is_moveable_with(with) {
+        with
+    } else {
+        with.fliped()
+    }
+}
+
+// 手を打つ前の局面と手番と手
+type Ply = (Osero, Pattern, (usize, usize));
+
+// 初期局面から手順を打ち、各手を打つ前の局面と手番と手を返す
+fn replay(moves: &[(usize, usize)]) -> Option<Vec<Ply>> {
+    let mut osero = Osero::default();
+    let mut with = Pattern::Black;
+    let mut positions = vec![];
+    for &at in moves {
+        if !osero.is_moveable_with(with) {
+            with = with.fliped();
+        }
+        if !osero.is_putabele(with, at) {
+            return None;
+        }
+        positions.push((osero, with, at));
+        osero.put(with, at);
+        with = with.fliped();
+    }
+    Some(positions)
+}
+
+// "12:+34" を (12, 34) にする
+fn parse_stats(word: &str) -> Option<(u32, i64)> {
+    let (games, score) = word.split_once(':')?;
+    Some((games.parse().ok()?, score.parse().ok()?))
+}
+
+impl Osero {
+    /// `book` に定石の手があればそこから選ぶ
+    pub fn book_move(&self, with: Pattern, book: &OpeningBook) -> Option<(usize, usize)> {
+        book.choose(self, with, &mut rand::rng())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> (usize, usize) {
+        s.parse::<Move>().unwrap().at().unwrap()
+    }
+
+    fn book(text: &str) -> OpeningBook {
+        let mut book = OpeningBook::new();
+        book.read_from(text.as_bytes()).unwrap();
+        book
+    }
+
+    fn after(moves: &str) -> (Osero, Pattern) {
+        let positions = replay(
+            &parse_moves(moves)
+                .unwrap()
+                .iter()
+                .filter_map(Move::at)
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+        let &(mut osero, with, at) = positions.last().unwrap();
+        osero.put(with, at);
+        (osero, next_turn(&osero, with))
+    }
+
+    #[test]
+    fn round_trips() {
+        let text = "# コメント\nf5d6 3:+12 Perpendicular\nf5f6 1:-4\nf5d6c3d3c4 Tiger\n";
+        let first = book(text);
+        let mut written = vec![];
+        first.write_to(&mut written).unwrap();
+
+        let second = book(std::str::from_utf8(&written).unwrap());
+        let mut rewritten = vec![];
+        second.write_to(&mut rewritten).unwrap();
+        assert_eq!(written, rewritten);
+        assert_eq!(second.len(), first.len());
+
+        let (osero, with) = after("f5");
+        // f5 のあとの d6 と f4 は対称な手なので、定石には片方だけが入る
+        let mut moves = second.moves(&osero, with);
+        moves.sort_by_key(|m| m.at);
+        assert_eq!(
+            moves,
+            [
+                BookMove {
+                    at: at("d6"),
+                    games: 3,
+                    score: 12
+                },
+                BookMove {
+                    at: at("f6"),
+                    games: 1,
+                    score: -4
+                },
+            ]
+        );
+        let (osero, with) = after("f5d6");
+        assert_eq!(second.name(&osero, with), Some("Perpendicular"));
+        let (osero, with) = after("f5d6c3d3c4");
+        assert_eq!(second.name(&osero, with), Some("Tiger"));
+    }
+
+    #[test]
+    fn rejects_illegal_lines() {
+        let mut book = OpeningBook::new();
+        let e = book.read_from("f5d6\nf5f5\n".as_bytes()).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        assert!(e.to_string().starts_with("2行目"));
+    }
+
+    #[test]
+    fn finds_rotated_openings() {
+        let book = book(include_str!("../books/openings.txt"));
+        let (osero, with) = after("d3c5");
+        assert_eq!(book.name(&osero, with), Some("Perpendicular"));
+        let (osero, with) = after("d3");
+        let moves = book.moves(&osero, with);
+        assert!(moves.iter().any(|m| m.at == at("c5")));
+        assert!(moves.iter().all(|m| osero.is_putabele(with, m.at)));
+    }
+
+    #[test]
+    fn learns_results() {
+        let mut book = OpeningBook::new();
+        // f5 だけ打った局面は黒 4 石、白 1 石。空きマスは勝った黒に数える
+        assert!(book.learn(&[at("f5")], 1));
+        // f5d6 のあとは黒 3 石、白 3 石で引き分け
+        assert!(book.learn(&[at("f5"), at("d6")], 1));
+        assert!(!book.learn(&[at("f5"), at("f5")], 2));
+
+        let moves = book.moves(&Osero::default(), Pattern::Black);
+        assert_eq!(
+            moves,
+            [BookMove {
+                at: at("f5"),
+                games: 2,
+                score: 62
+            }]
+        );
+        // 覚えるのは最初の depth 手まで
+        let (osero, with) = after("f5");
+        assert!(book.moves(&osero, with).is_empty());
+    }
+
+    #[test]
+    fn chooses_within_randomness() {
+        let text = "f5d6 10:+20\nf5f6 10:+0\n";
+        let (osero, with) = after("f5");
+        let chosen = |randomness: u32| {
+            let book = book(text).with_randomness(randomness);
+            (0..64)
+                .map(|seed| {
+                    book.choose(&osero, with, &mut StdRng::seed_from_u64(seed))
+                        .unwrap()
+                })
+                .collect::<HashSet<_>>()
+        };
+        assert_eq!(chosen(0), HashSet::from([at("d6")]));
+        assert_eq!(chosen(1), HashSet::from([at("d6")]));
+        assert_eq!(chosen(2), HashSet::from([at("d6"), at("f6")]));
+        // 定石から外れた局面では選ばない
+        let (osero, with) = after("f5d6c3");
+        assert_eq!(book(text).choose(&osero, with, &mut rand::rng()), None);
+    }
+}
@@ -0,0 +1,138 @@
+use std::time::Duration;
+
+use osero::{Engine, Game, Move, Osero, Pattern};
+
+use super::play::color_name;
+use super::{engine_for, load_record, value};
+
+const USAGE: &str = "使い方: osero analyze [--depth N | --time ミリ秒] [--heatmap] [--ai AI]
+                     [--position 局面 | --load ファイル]
+  局面の合法手をすべて読み、評価のよい順に表にする
+  --depth N        それぞれの手を N 手先まで読む（既定 8）
+  --time ミリ秒    全部の手をあわせてこの時間だけ読む。合法手で等分する
+  --heatmap        盤面に順位と評価の色を重ねて表示する
+  --ai AI          standard（既定）、positional、重みのファイル
+  --position 局面  この局面を調べる（書き方は osero --help）。省くと初期局面
+  --load ファイル  棋譜の最後の局面を調べる";
+
+/// 深さを指定しなければこの深さまで読む
+pub const ANALYZE_DEPTH: usize = 8;
+
+/// 評価のよい手から悪い手への色（256 色の背景色。緑から赤）
+const HEATMAP_COLORS: [u8; 11] = [46, 82, 118, 154, 190, 226, 220, 214, 208, 202, 196];
+
+/// すべての合法手を読んで表にする。`heatmap` なら盤面に評価を重ねて表示する
+pub fn print_analysis(
+    engine: &Engine,
+    osero: &Osero,
+    with: Pattern,
+    depth: Option<usize>,
+    move_time: Option<Duration>,
+    heatmap: bool,
+) {
+    let results = match (depth, move_time) {
+        (None, Some(limit)) => engine.search_moves_timed(osero, with, limit),
+        (depth, _) => engine.search_moves(osero, with, depth.unwrap_or(ANALYZE_DEPTH)),
+    };
+    if results.is_empty() {
+        println!("{}は置ける手がありません。", color_name(with));
+        return;
+    }
+
+    println!("順位  手    評価値     石差  深さ  読み筋");
+    for (rank, result) in results.iter().enumerate() {
+        let discs = match result.disc_difference() {
+            Some(discs) => format!("{:+} 読み切り", discs),
+            None => format!("{:+.1}", engine.discs(result)),
+        };
+        let pv: Vec<String> = result.pv.iter().map(Move::to_string).collect();
+        println!(
+            "{:>4}  {:<4} {:>8}  {:>7}  {:>4}  {}",
+            rank + 1,
+            result.best(),
+            result.score,
+            discs,
+            result.depth,
+            pv.join(" ")
+        );
+    }
+
+    if heatmap {
+        let ranked: Vec<((usize, usize), i32)> = results
+            .iter()
+            .filter_map(|result| Some((result.best_move?, result.score)))
+            .collect();
+        println!("\n{}", heatmap_string(osero, &ranked));
+        println!("数字は順位（10 位から下は *）。緑ほど評価がよく、赤ほど悪い");
+    }
+}
+
+// `express()` の盤面の合法手のマスを、順位と評価の色に置きかえる。`ranked` はよい順
+fn heatmap_string(osero: &Osero, ranked: &[((usize, usize), i32)]) -> String {
+    let best = ranked.first().map_or(0, |&(_, score)| score);
+    let worst = ranked.last().map_or(0, |&(_, score)| score);
+    let mut lines: Vec<String> = osero.express().lines().map(str::to_string).collect();
+    // 色をつけると行が長くなるので、右のマスから置きかえる
+    let mut cells: Vec<_> = ranked.iter().enumerate().collect();
+    cells.sort_by_key(|&(_, &((x, _), _))| std::cmp::Reverse(x));
+    for (rank, &((x, y), score)) in cells {
+        let shade = if best == worst {
+            0
+        } else {
+            ((best - score) as i64 * (HEATMAP_COLORS.len() - 1) as i64 / (best - worst) as i64)
+                as usize
+        };
+        let mark = match rank + 1 {
+            rank @ 1..=9 => rank.to_string(),
+            _ => "*".to_string(),
+        };
+        let cell = format!("\x1b[30;48;5;{}m{}\x1b[0m", HEATMAP_COLORS[shade], mark);
+        // 1行目は a〜h のラベル。各行は行番号と空白のあとに「マス 空白」が続く
+        let line = &mut lines[y + 1];
+        line.replace_range(2 + 2 * x..3 + 2 * x, &cell);
+    }
+    lines.join("\n")
+}
+
+pub fn run(args: Vec<String>) -> Result<(), String> {
+    let mut depth = None;
+    let mut move_time = None;
+    let mut heatmap = false;
+    let mut ai = "standard".to_string();
+    let mut game = Game::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--depth" => depth = Some(value(&mut args, "--depth")?),
+            "--time" => move_time = Some(Duration::from_millis(value(&mut args, "--time")?)),
+            "--heatmap" => heatmap = true,
+            "--ai" | "--eval" => ai = value(&mut args, &arg)?,
+            "--position" => {
+                let position: String = value(&mut args, "--position")?;
+                let (osero, turn) = Osero::parse_position(&position).map_err(|e| e.to_string())?;
+                game = Game::from_position(osero, turn);
+            }
+            "--load" => game = load_record(&value::<String>(&mut args, "--load")?)?.game,
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            _ => return Err(format!("{} は知らないオプションです\n{}", arg, USAGE)),
+        }
+    }
+    if ai.starts_with("mcts") {
+        return Err("analyze では αβ探索の AI しか使えません".to_string());
+    }
+    let engine = engine_for(&ai)?;
+
+    if game.is_finished() {
+        return Err("終局した局面です".to_string());
+    }
+    let passed = game.turn();
+    if game.pass_if_forced() {
+        println!("{}は置けないのでパスします。", color_name(passed));
+    }
+    let osero = *game.board();
+    let with = game.turn();
+    println!("{}", osero.express());
+    println!("{}の番", color_name(with));
+    print_analysis(&engine, &osero, with, depth, move_time, heatmap);
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use osero::OpeningBook;
+
+use super::{engine_for, load_book, value};
+
+const USAGE: &str = "使い方: osero book --output ファイル [オプション]
+  自己対局の結果から定石の手の成績を覚え、定石を広げて書き出す
+  --output ファイル  覚えた定石の書き出し先（必須）
+  --book ファイル    元にする定石（省くと空の定石から始める）
+  --games N          自己対局の数（既定 100）
+  --level N          定石から外れたあとに読む深さ（既定 4）
+  --depth N          最初の何手を覚えるか（既定 12）
+  --book-random N    最善の定石から1局あたり N 石差以内の手まで試す（既定 4）
+  --seed N           乱数の種。同じ種なら同じ対局になる（既定 0）
+  --eval 評価関数    自己対局に使う評価関数（既定 standard）";
+
+pub fn run(args: Vec<String>) -> Result<(), String> {
+    let mut output: Option<String> = None;
+    let mut book_path: Option<String> = None;
+    let mut games = 100;
+    let mut level = 4;
+    let mut depth = 12;
+    let mut randomness = 4;
+    let mut seed = 0;
+    let mut eval = "standard".to_string();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" | "-o" => output = Some(value(&mut args, "--output")?),
+            "--book" => book_path = Some(value(&mut args, "--book")?),
+            "--games" => games = value(&mut args, "--games")?,
+            "--level" => level = value(&mut args, "--level")?,
+            "--depth" => depth = value(&mut args, "--depth")?,
+            "--book-random" => randomness = value(&mut args, "--book-random")?,
+            "--seed" => seed = value(&mut args, "--seed")?,
+            "--eval" => eval = value(&mut args, "--eval")?,
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            _ => return Err(format!("{} は知らないオプションです\n{}", arg, USAGE)),
+        }
+    }
+    let output = output.ok_or(USAGE.to_string())?;
+
+    let mut book = match book_path {
+        Some(path) => load_book(&path, randomness)?,
+        None => OpeningBook::new().with_randomness(randomness),
+    };
+    // 評価関数が読めるかを先に確かめておく
+    engine_for(&eval)?;
+    println!(
+        "自己対局を {} 局打って最初の {} 手を覚えます（深さ {}）",
+        games, depth, level
+    );
+    let engine = || engine_for(&eval).unwrap_or_default();
+    book.learn_by_self_play(engine, games, level, depth, seed, |done| {
+        if done % 16 == 0 || done == games {
+            println!("{:>5} 局", done);
+        }
+    });
+
+    book.save(&output)
+        .map_err(|e| format!("定石を {} に保存できません: {}", output, e))?;
+    println!("{} 局面の定石を {} に書き出しました", book.len(), output);
+    Ok(())
+}
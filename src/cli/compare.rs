@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use osero::{Osero, Pattern};
+use rand::seq::IndexedRandom;
+
+use super::{ai_for, value};
+
+const USAGE: &str =
+    "使い方: osero compare [--games N] [--level N | --time ミリ秒] [--random N] AI_A [AI_B]
+  AI は standard、positional、重みのファイル（αβ探索の評価関数）、または mcts、mcts-random。
+  B を省くと positional（POSITION_SCORE）と比べる
+  --games N        対局数。先手と後手を交互に入れ替える（既定 20）
+  --level N        読む深さ（既定 4）
+  --time ミリ秒    深さの代わりに1手に考える時間
+  --random N       最初の N 手はランダムに打って局面をばらけさせる（既定 4）";
+
+pub fn run(args: Vec<String>) -> Result<(), String> {
+    let mut games = 20;
+    let mut level = 4;
+    let mut move_time = None;
+    let mut random = 4;
+    let mut names = vec![];
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--games" => games = value(&mut args, "--games")?,
+            "--level" => level = value(&mut args, "--level")?,
+            "--time" => move_time = Some(Duration::from_millis(value(&mut args, "--time")?)),
+            "--random" => random = value(&mut args, "--random")?,
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            _ if arg.starts_with("--") => {
+                return Err(format!("{} は知らないオプションです\n{}", arg, USAGE));
+            }
+            _ => names.push(arg),
+        }
+    }
+    let (a, b) = match names.as_slice() {
+        [a] => (a.clone(), "positional".to_string()),
+        [a, b] => (a.clone(), b.clone()),
+        _ => return Err(USAGE.to_string()),
+    };
+    let ais = [ai_for(&a)?, ai_for(&b)?];
+
+    // A から見た [勝ち, 負け, 引き分け] と石差の合計
+    let mut results = [0; 3];
+    let mut total = 0;
+    for game in 0..games {
+        // 偶数局は A が黒、奇数局は A が白
+        let a_color = if game % 2 == 0 {
+            Pattern::Black
+        } else {
+            Pattern::White
+        };
+        let mut osero = Osero::default();
+        let mut with = Pattern::Black;
+        let mut rng = rand::rng();
+        for _ in 0..random {
+            if let Some(at) = osero.get_moveable_points(with).choose(&mut rng) {
+                osero.put(with, *at);
+            }
+            with = with.fliped();
+        }
+        while !osero.is_finished() {
+            let ai = &ais[usize::from(with != a_color)];
+            if let Some(at) = ai.think(&osero, with, level, move_time) {
+                osero.put(with, at);
+            }
+            with = with.fliped();
+        }
+
+        let (black, white, _) = osero.many();
+        let diff = if a_color == Pattern::Black {
+            black as i32 - white as i32
+        } else {
+            white as i32 - black as i32
+        };
+        total += diff;
+        results[match diff {
+            d if d > 0 => 0,
+            d if d < 0 => 1,
+            _ => 2,
+        }] += 1;
+        println!(
+            "{}局目: A（{}）{:+}　黒 {} - 白 {}",
+            game + 1,
+            a_color,
+            diff,
+            black,
+            white
+        );
+    }
+
+    println!("\nA: {}\nB: {}", a, b);
+    println!(
+        "A の {}勝 {}敗 {}分　平均石差 {:+.1}",
+        results[0],
+        results[1],
+        results[2],
+        total as f64 / games.max(1) as f64
+    );
+    Ok(())
+}
@@ -0,0 +1,193 @@
+use std::io::{BufRead, Write};
+use std::time::Duration;
+
+use osero::{Game, Move, Pattern};
+
+use super::{Ai, ai_for, load_book, value};
+
+const USAGE: &str =
+    "使い方: osero --gtp [--level N] [--time ミリ秒] [--ai AI] [--book ファイル [--book-random N]]
+  GTP に似たコマンドで標準入出力からやりとりする。返事は成功なら `=`、失敗なら `?` で始まる
+  --level N        genmove で考えるレベル（既定 4）
+  --time ミリ秒    genmove で1手に考える時間
+  --ai AI          standard（既定）、positional、重みのファイル、mcts、mcts-random
+  --book ファイル  定石のファイル
+  --book-random N  最善の定石から1局あたり N 石差以内の手まで候補にする（既定 0）";
+
+const COMMANDS: [&str; 14] = [
+    "protocol_version",
+    "name",
+    "version",
+    "known_command",
+    "list_commands",
+    "boardsize",
+    "clear_board",
+    "komi",
+    "play",
+    "genmove",
+    "undo",
+    "showboard",
+    "final_score",
+    "quit",
+];
+
+struct Gtp {
+    ai: Ai,
+    level: usize,
+    move_time: Option<Duration>,
+    game: Game,
+}
+
+impl Gtp {
+    /// 1つのコマンドを処理して、`=` や `?` を除いた返事を返す
+    fn handle(&mut self, command: &str, args: &[&str]) -> Result<String, String> {
+        match command {
+            "protocol_version" => Ok("2".to_string()),
+            "name" => Ok("osero".to_string()),
+            "version" => Ok(env!("CARGO_PKG_VERSION").to_string()),
+            "known_command" => {
+                let known = args.first().is_some_and(|name| COMMANDS.contains(name));
+                Ok(known.to_string())
+            }
+            "list_commands" => Ok(COMMANDS.join("\n")),
+            "boardsize" => match args.first().map(|size| size.parse::<usize>()) {
+                Some(Ok(8)) => Ok(String::new()),
+                _ => Err("unacceptable size".to_string()),
+            },
+            "clear_board" => {
+                self.game = Game::new();
+                Ok(String::new())
+            }
+            // 引き分けの扱いは変えない
+            "komi" => Ok(String::new()),
+            "play" => {
+                let (with, mv) = match args {
+                    [color, mv] => (parse_color(color)?, *mv),
+                    _ => return Err("syntax error".to_string()),
+                };
+                let mv: Move = mv.parse().map_err(|_| "syntax error".to_string())?;
+                self.pass_until(with);
+                if self.game.turn() != with || !self.game.play(mv) {
+                    return Err("illegal move".to_string());
+                }
+                Ok(String::new())
+            }
+            "genmove" => {
+                let with = match args {
+                    [color] => parse_color(color)?,
+                    _ => return Err("syntax error".to_string()),
+                };
+                self.pass_until(with);
+                if self.game.is_finished() || self.game.turn() != with {
+                    return Ok(Move::Pass.to_string());
+                }
+                let osero = *self.game.board();
+                let mv = Move::from_at(self.ai.think(&osero, with, self.level, self.move_time));
+                self.game.play(mv);
+                Ok(mv.to_string())
+            }
+            "undo" => {
+                self.game.undo().ok_or("cannot undo")?;
+                // 自動で入れたパスもまとめて戻す
+                while self.game.moves().last() == Some(&Move::Pass) {
+                    self.game.undo();
+                }
+                Ok(String::new())
+            }
+            "showboard" => {
+                let osero = self.game.board();
+                let (black, white, _) = osero.many();
+                Ok(format!(
+                    "\n{}X: {}  O: {}  to move: {}",
+                    osero.express(),
+                    black,
+                    white,
+                    self.game.turn()
+                ))
+            }
+            "final_score" => {
+                let (black, white, _) = self.game.board().many();
+                let diff = self.game.result().unwrap_or(black as i32 - white as i32);
+                Ok(match diff {
+                    0 => "0".to_string(),
+                    d if d > 0 => format!("B+{}", d),
+                    d => format!("W+{}", -d),
+                })
+            }
+            "quit" => Ok(String::new()),
+            _ => Err("unknown command".to_string()),
+        }
+    }
+
+    // `with` の番でなく、相手が置けないならパスを入れる
+    fn pass_until(&mut self, with: Pattern) {
+        if self.game.turn() != with {
+            self.game.pass_if_forced();
+        }
+    }
+}
+
+fn parse_color(color: &str) -> Result<Pattern, String> {
+    match color.to_ascii_lowercase().as_str() {
+        "black" | "b" => Ok(Pattern::Black),
+        "white" | "w" => Ok(Pattern::White),
+        _ => Err("invalid color".to_string()),
+    }
+}
+
+pub fn run(args: Vec<String>) -> Result<(), String> {
+    let mut level = 4;
+    let mut move_time = None;
+    let mut ai_name = "standard".to_string();
+    let mut book_path: Option<String> = None;
+    let mut book_randomness = 0;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--level" => level = value(&mut args, "--level")?,
+            "--time" => move_time = Some(Duration::from_millis(value(&mut args, "--time")?)),
+            "--ai" | "--eval" => ai_name = value(&mut args, &arg)?,
+            "--book" => book_path = Some(value(&mut args, "--book")?),
+            "--book-random" => book_randomness = value(&mut args, "--book-random")?,
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            _ => return Err(format!("{} は知らないオプションです\n{}", arg, USAGE)),
+        }
+    }
+    let mut ai = ai_for(&ai_name)?;
+    if let Some(path) = &book_path {
+        ai = ai.with_book(load_book(path, book_randomness)?)?;
+    }
+    let mut gtp = Gtp {
+        ai,
+        level,
+        move_time,
+        game: Game::new(),
+    };
+
+    let stdin = std::io::stdin();
+    let mut out = std::io::stdout();
+    for line in stdin.lock().lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        // # から後ろはコメント
+        let line = line.split('#').next().unwrap_or_default();
+        let mut words: Vec<&str> = line.split_whitespace().collect();
+        // 先頭の数字はコマンドの番号。返事にもつける
+        let id = match words.first() {
+            Some(word) if word.chars().all(|c| c.is_ascii_digit()) => words.remove(0),
+            _ => "",
+        };
+        let Some((&command, args)) = words.split_first() else {
+            continue;
+        };
+        let response = match gtp.handle(command, args) {
+            Ok(message) => format!("={} {}", id, message),
+            Err(message) => format!("?{} {}", id, message),
+        };
+        write!(out, "{}\n\n", response.trim_end()).map_err(|e| e.to_string())?;
+        out.flush().map_err(|e| e.to_string())?;
+        if command == "quit" {
+            break;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,153 @@
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use osero::{
+    Engine, GgfGame, Mcts, MoveNote, OpeningBook, Osero, Pattern, PatternEvaluator, Playout,
+    PositionalEvaluator, Record,
+};
+
+pub mod analyze;
+pub mod book;
+pub mod compare;
+pub mod gtp;
+pub mod nboard;
+pub mod online;
+pub mod play;
+pub mod serve;
+pub mod train;
+pub mod tui;
+pub mod wthor;
+
+/// `--eval` の値から探索エンジンを作る。standard / positional 以外は重みのファイルとして読む
+pub fn engine_for(eval: &str) -> Result<Engine, String> {
+    let engine = Engine::default();
+    match eval {
+        "standard" => Ok(engine),
+        "positional" => Ok(engine.with_evaluator(PositionalEvaluator)),
+        path => {
+            let evaluator = PatternEvaluator::load(path)
+                .map_err(|e| format!("重みのファイル {} を読めません: {}", path, e))?;
+            Ok(engine.with_evaluator(evaluator))
+        }
+    }
+}
+
+/// αβ探索か MCTS のどちらかで手を選ぶ AI
+pub enum Ai {
+    Search(Engine),
+    Mcts(Mcts),
+}
+
+impl Ai {
+    /// 定石にある局面では定石の手を打つようにする。αβ探索の AI だけが定石を使える
+    pub fn with_book(self, book: OpeningBook) -> Result<Ai, String> {
+        match self {
+            Ai::Search(engine) => Ok(Ai::Search(engine.with_book(book))),
+            Ai::Mcts(_) => Err("定石は αβ探索の AI でしか使えません".to_string()),
+        }
+    }
+
+    /// この局面につけられた定石の名前
+    pub fn opening_name(&self, osero: &Osero, with: Pattern) -> Option<&str> {
+        match self {
+            Ai::Search(engine) => engine.book()?.name(osero, with),
+            Ai::Mcts(_) => None,
+        }
+    }
+
+    /// MCTS ではレベル 1 あたりこの回数だけプレイアウトする
+    const PLAYOUTS_PER_LEVEL: u64 = 2000;
+
+    /// `move_time` があればその時間だけ、なければ `level` に応じて考える
+    pub fn think(
+        &self,
+        osero: &Osero,
+        with: Pattern,
+        level: usize,
+        move_time: Option<Duration>,
+    ) -> Option<(usize, usize)> {
+        match (self, move_time) {
+            (Ai::Search(engine), Some(limit)) => engine.search_timed(osero, with, limit).best_move,
+            (Ai::Search(engine), None) => engine.best_move(osero, with, level),
+            (Ai::Mcts(mcts), Some(limit)) => mcts.search_timed(osero, with, limit).best_move,
+            (Ai::Mcts(mcts), None) => {
+                let playouts = (level.max(1) as u64).saturating_mul(Self::PLAYOUTS_PER_LEVEL);
+                mcts.search(osero, with, playouts).best_move
+            }
+        }
+    }
+
+    /// `think` と同じく手を選び、棋譜に残す評価値（打つ側から見た石差）とかかった時間を添える。
+    /// 定石の手や MCTS の手には評価値をつけない
+    pub fn think_with_note(
+        &self,
+        osero: &Osero,
+        with: Pattern,
+        level: usize,
+        move_time: Option<Duration>,
+    ) -> (Option<(usize, usize)>, MoveNote) {
+        let start = Instant::now();
+        let (best, eval) = match self {
+            Ai::Search(engine) if move_time.is_some() || level > 1 => {
+                let result = match move_time {
+                    Some(limit) => engine.search_timed(osero, with, limit),
+                    None => engine.search(osero, with, level),
+                };
+                let eval = (result.depth > 0).then(|| engine.discs(&result));
+                (result.best_move, eval)
+            }
+            _ => (self.think(osero, with, level, move_time), None),
+        };
+        let time = Some(start.elapsed().as_secs_f64());
+        (best, MoveNote { eval, time })
+    }
+}
+
+/// AI の名前から AI を作る。mcts / mcts-random はモンテカルロ木探索、それ以外は `engine_for` に渡す
+pub fn ai_for(name: &str) -> Result<Ai, String> {
+    match name {
+        "mcts" => Ok(Ai::Mcts(Mcts::default())),
+        "mcts-random" => Ok(Ai::Mcts(Mcts::default().with_playout(Playout::Random))),
+        eval => engine_for(eval).map(Ai::Search),
+    }
+}
+
+/// 定石のファイルを読む
+pub fn load_book(path: &str, randomness: u32) -> Result<OpeningBook, String> {
+    OpeningBook::load(path)
+        .map(|book| book.with_randomness(randomness))
+        .map_err(|e| format!("定石のファイル {} を読めません: {}", path, e))
+}
+
+/// 棋譜を保存する。拡張子が .ggf なら GGF で書き、`notes` の評価値と時間を手に添える
+pub fn save_record(record: Record, notes: &[MoveNote], path: &str) -> std::io::Result<()> {
+    if path.ends_with(".ggf") {
+        let mut ggf = GgfGame::from(record);
+        for (slot, note) in ggf.notes.iter_mut().zip(notes) {
+            *slot = *note;
+        }
+        std::fs::write(path, format!("{}\n", ggf))
+    } else {
+        record.save(path)
+    }
+}
+
+/// 棋譜を読む。拡張子が .ggf なら GGF の最初の対局を読む
+pub fn load_record(path: &str) -> Result<Record, String> {
+    if path.ends_with(".ggf") {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let games = GgfGame::parse_all(&text).map_err(|e| e.to_string())?;
+        let game = games.into_iter().next().ok_or("対局がありません")?;
+        Ok(game.record)
+    } else {
+        Record::load(path).map_err(|e| e.to_string())
+    }
+}
+
+/// オプション `name` の値を次の引数から読む
+pub fn value<T: FromStr>(args: &mut impl Iterator<Item = String>, name: &str) -> Result<T, String> {
+    let value = args.next().ok_or(format!("{} の値がありません", name))?;
+    value
+        .parse::<T>()
+        .map_err(|_| format!("{} の値 {} は無効です", name, value))
+}
@@ -0,0 +1,238 @@
+use std::io::{BufRead, Write};
+use std::time::Instant;
+
+use osero::{Engine, Game, GgfGame, Move, OpeningBook, Osero, Pattern, SearchResult};
+
+use super::{engine_for, load_book, value};
+
+const USAGE: &str = "使い方: osero --nboard [--ai 評価関数] [--book ファイル [--book-random N]]
+  NBoard のエンジンとして標準入出力で GUI とやりとりする
+  --ai 評価関数    standard（既定）、positional、重みのファイル
+  --book ファイル  定石のファイル。learn を受けると対局を覚えてこのファイルに書き出す
+  --book-random N  最善の定石から1局あたり N 石差以内の手まで候補にする（既定 0）";
+
+/// 読む深さを指定されるまではこの深さで読む
+const DEFAULT_DEPTH: usize = 8;
+/// learn で定石に覚える手数
+const LEARN_DEPTH: usize = 20;
+
+struct NBoard {
+    eval: String,
+    book: Option<(OpeningBook, String)>,
+    engine: Engine,
+    game: Game,
+    depth: usize,
+}
+
+impl NBoard {
+    fn new(eval: String, book: Option<(OpeningBook, String)>) -> Result<Self, String> {
+        Ok(NBoard {
+            engine: new_engine(&eval, &book)?,
+            eval,
+            book,
+            game: Game::new(),
+            depth: DEFAULT_DEPTH,
+        })
+    }
+
+    /// 1行のコマンドを処理して返事を書く。quit なら false を返す
+    fn handle(&mut self, line: &str, out: &mut impl Write) -> Result<bool, String> {
+        let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let rest = rest.trim();
+        match command {
+            "nboard" => writeln!(out, "set myname osero").map_err(|e| e.to_string())?,
+            "set" => self.set(rest)?,
+            "move" => {
+                // "f5/1.23/4.5" の評価値と時間は使わない
+                let mv: Move = rest
+                    .split('/')
+                    .next()
+                    .unwrap_or_default()
+                    .parse()
+                    .map_err(|e| format!("{}", e))?;
+                if mv != Move::Pass {
+                    self.game.pass_if_forced();
+                }
+                if !self.game.play(mv) {
+                    return Err(format!("{} は打てません", mv));
+                }
+            }
+            "go" => self.go(out).map_err(|e| e.to_string())?,
+            "hint" => {
+                let count: usize = rest
+                    .parse()
+                    .map_err(|_| format!("hint の数 {} が読めません", rest))?;
+                self.hint(count, out).map_err(|e| e.to_string())?;
+            }
+            "learn" => {
+                self.learn()?;
+                writeln!(out, "learned").map_err(|e| e.to_string())?;
+            }
+            "ping" => writeln!(out, "pong {}", rest).map_err(|e| e.to_string())?,
+            "quit" => return Ok(false),
+            _ => return Err(format!("{} は知らないコマンドです", command)),
+        }
+        Ok(true)
+    }
+
+    fn set(&mut self, rest: &str) -> Result<(), String> {
+        let (name, value) = rest.split_once(' ').unwrap_or((rest, ""));
+        match name {
+            "depth" => {
+                self.depth = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("深さ {} が読めません", value))?;
+            }
+            "game" => {
+                let ggf: GgfGame = value.parse().map_err(|e| format!("{}", e))?;
+                self.game = ggf.record.game;
+            }
+            // 引き分けの扱いは変えない
+            "contempt" => {}
+            _ => return Err(format!("set {} には対応していません", name)),
+        }
+        Ok(())
+    }
+
+    fn go(&self, out: &mut impl Write) -> std::io::Result<()> {
+        let osero = self.game.board();
+        let with = self.game.turn();
+        if self.game.legal_moves() == [Move::Pass] {
+            return writeln!(out, "=== {}", Move::Pass);
+        }
+        writeln!(out, "status 考え中")?;
+        out.flush()?;
+        let start = Instant::now();
+        let result = self.engine.search(osero, with, self.depth);
+        let seconds = start.elapsed().as_secs_f64();
+        writeln!(out, "nodestats {} {:.3}", result.nodes, seconds)?;
+        writeln!(out, "status")?;
+        let mv = Move::from_at(result.best_move);
+        writeln!(out, "=== {}/{:.2}/{:.3}", mv, self.discs(&result), seconds)
+    }
+
+    // 定石の手なら定石の成績を評価値にする
+    fn discs(&self, result: &SearchResult) -> f64 {
+        if result.depth > 0 {
+            return self.engine.discs(result);
+        }
+        let book = self.engine.book().map(|book| {
+            book.moves(self.game.board(), self.game.turn())
+                .into_iter()
+                .find(|book_move| Some(book_move.at) == result.best_move)
+        });
+        match book.flatten() {
+            Some(book_move) => book_move.average(),
+            None => self.engine.discs(result),
+        }
+    }
+
+    /// 定石の手を `book`、読んだ手を `search` の行で、よい順に `count` 手まで知らせる
+    fn hint(&self, count: usize, out: &mut impl Write) -> std::io::Result<()> {
+        let osero = self.game.board();
+        let with = self.game.turn();
+        writeln!(out, "status 考え中")?;
+        out.flush()?;
+        let book_moves = match self.engine.book() {
+            Some(book) => book.moves(osero, with),
+            None => vec![],
+        };
+        for book_move in book_moves.iter().take(count) {
+            writeln!(
+                out,
+                "book {} {:.2} {}",
+                Move::from(book_move.at),
+                book_move.average(),
+                book_move.games
+            )?;
+        }
+        let rest = count.saturating_sub(book_moves.len());
+        if rest > 0 {
+            let results = self.engine.search_moves(osero, with, self.depth);
+            let searched = results
+                .iter()
+                .filter(|result| book_moves.iter().all(|b| Some(b.at) != result.best_move));
+            for result in searched.take(rest) {
+                let depth = if result.exact {
+                    "100%".to_string()
+                } else {
+                    result.depth.to_string()
+                };
+                writeln!(
+                    out,
+                    "search {} {:.2} 0 {}",
+                    Move::from_at(result.best_move),
+                    self.engine.discs(result),
+                    depth
+                )?;
+            }
+        }
+        writeln!(out, "status")
+    }
+
+    // 今の対局を定石に覚え、定石のファイルに書き出す
+    fn learn(&mut self) -> Result<(), String> {
+        let Some((book, path)) = &mut self.book else {
+            return Ok(());
+        };
+        if self.game.start() != (&Osero::default(), Pattern::Black) {
+            eprintln!("途中の局面から始めた対局は覚えません");
+            return Ok(());
+        }
+        let moves: Vec<(usize, usize)> = self.game.moves().iter().filter_map(Move::at).collect();
+        book.learn(&moves, LEARN_DEPTH);
+        book.save(&*path)
+            .map_err(|e| format!("定石を {} に保存できません: {}", path, e))?;
+        // 覚え直した定石でエンジンを作り直す
+        self.engine = new_engine(&self.eval, &self.book)?;
+        Ok(())
+    }
+}
+
+fn new_engine(eval: &str, book: &Option<(OpeningBook, String)>) -> Result<Engine, String> {
+    let engine = engine_for(eval)?;
+    Ok(match book {
+        Some((book, _)) => engine.with_book(book.clone()),
+        None => engine,
+    })
+}
+
+pub fn run(args: Vec<String>) -> Result<(), String> {
+    let mut eval = "standard".to_string();
+    let mut book_path: Option<String> = None;
+    let mut book_randomness = 0;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--ai" | "--eval" => eval = value(&mut args, &arg)?,
+            "--book" => book_path = Some(value(&mut args, "--book")?),
+            "--book-random" => book_randomness = value(&mut args, "--book-random")?,
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            _ => return Err(format!("{} は知らないオプションです\n{}", arg, USAGE)),
+        }
+    }
+    let book = match book_path {
+        Some(path) => Some((load_book(&path, book_randomness)?, path)),
+        None => None,
+    };
+    let mut nboard = NBoard::new(eval, book)?;
+
+    let stdin = std::io::stdin();
+    let mut out = std::io::stdout();
+    for line in stdin.lock().lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        match nboard.handle(line, &mut out) {
+            Ok(true) => {}
+            Ok(false) => break,
+            // GUI には知らせず、エラーだけ出して続ける
+            Err(message) => eprintln!("{}", message),
+        }
+        out.flush().map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,535 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+
+use osero::{Game, Move, Osero, Pattern};
+use serde_json::{Value, json};
+use tungstenite::{Message, WebSocket};
+
+use super::serve::{color, game_json};
+use super::value;
+
+const USAGE: &str = "使い方: osero online serve [--port N]
+       osero online join [--room 部屋] [--name 名前] [--token 合言葉] アドレス
+  LAN の中で人どうしが WebSocket で対局する
+  serve            部屋を用意して待ち受ける（既定のポート 9001）
+  join アドレス    サーバー（例: 192.168.0.10:9001）に入る。部屋を省くと相手を待っている部屋に入る
+  --room 部屋      この名前の部屋に入る。なければ作る
+  --name 名前      相手に見せる名前
+  --token 合言葉   切断された対局に戻る（入ったときに表示される）";
+
+/// 読み込みを待つ時間。この間隔で相手からの知らせを送る
+const POLL: Duration = Duration::from_millis(50);
+/// 切断されたときに接続し直す回数
+const RECONNECT_TRIES: usize = 5;
+
+pub fn run(args: Vec<String>) -> Result<(), String> {
+    let mut args = args.into_iter();
+    match args.next().as_deref() {
+        Some("serve") => serve(args),
+        Some("join") => join(args),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+// 以下サーバー
+
+struct Seat {
+    name: String,
+    token: String,
+    // 接続していなければ None。`connection` で自分の接続かを見分ける
+    sender: Option<(u64, Sender<String>)>,
+}
+
+struct Room {
+    game: Game,
+    // [黒, 白]
+    seats: [Option<Seat>; 2],
+    resigned: Option<Pattern>,
+}
+
+impl Room {
+    fn new() -> Self {
+        Room {
+            game: Game::new(),
+            seats: [None, None],
+            resigned: None,
+        }
+    }
+
+    fn is_over(&self) -> bool {
+        self.resigned.is_some() || self.game.is_finished()
+    }
+
+    fn send(&self, index: usize, message: &Value) {
+        if let Some(Seat {
+            sender: Some((_, sender)),
+            ..
+        }) = &self.seats[index]
+        {
+            sender.send(message.to_string()).ok();
+        }
+    }
+
+    fn broadcast(&self, message: &Value) {
+        for index in 0..2 {
+            self.send(index, message);
+        }
+    }
+
+    fn state(&self, name: &str) -> Value {
+        let player = |index: usize| self.seats[index].as_ref().map(|seat| seat.name.clone());
+        let connected = |index: usize| {
+            self.seats[index]
+                .as_ref()
+                .is_some_and(|seat| seat.sender.is_some())
+        };
+        json!({
+            "type": "state",
+            "room": name,
+            "game": game_json(&self.game),
+            "black": player(0),
+            "white": player(1),
+            "connected": [connected(0), connected(1)],
+            "resigned": self.resigned.map(color),
+        })
+    }
+}
+
+struct Lobby {
+    rooms: Mutex<HashMap<String, Room>>,
+    next_id: Mutex<u64>,
+    next_room: Mutex<u64>,
+}
+
+impl Lobby {
+    fn next_id(&self) -> u64 {
+        let mut next_id = self.next_id.lock().unwrap();
+        *next_id += 1;
+        *next_id
+    }
+
+    /// 席に着く。部屋の名前と席の番号を返す
+    fn join(
+        &self,
+        message: &Value,
+        connection: u64,
+        sender: &Sender<String>,
+    ) -> Result<(String, usize), String> {
+        let mut rooms = self.rooms.lock().unwrap();
+        let room_name = message["room"].as_str().map(str::to_string);
+        let name = message["name"].as_str().unwrap_or("名無し").to_string();
+
+        // 合言葉があれば切断された席に戻る
+        if let Some(token) = message["token"].as_str() {
+            for (room_name, room) in rooms.iter_mut() {
+                for (index, seat) in room.seats.iter_mut().enumerate() {
+                    if let Some(seat) = seat
+                        && seat.token == token
+                    {
+                        seat.sender = Some((connection, sender.clone()));
+                        room.send(1 - index, &json!({ "type": "opponent_returned" }));
+                        return Ok((room_name.clone(), index));
+                    }
+                }
+            }
+            return Err("合言葉に合う席がありません".to_string());
+        }
+
+        let room_name = match room_name {
+            Some(room_name) => room_name,
+            // 相手を待っている部屋を探し、なければ新しく作る
+            None => {
+                let waiting = rooms
+                    .iter()
+                    .filter(|(_, room)| !room.is_over())
+                    .filter(|(_, room)| {
+                        room.seats.iter().filter(|seat| seat.is_some()).count() == 1
+                    })
+                    .map(|(name, _)| name.clone())
+                    .min();
+                waiting.unwrap_or_else(|| {
+                    let mut next_room = self.next_room.lock().unwrap();
+                    *next_room += 1;
+                    format!("room-{}", next_room)
+                })
+            }
+        };
+        let room = rooms.entry(room_name.clone()).or_insert_with(Room::new);
+        let Some(index) = room.seats.iter().position(Option::is_none) else {
+            return Err(format!("部屋 {} は満員です", room_name));
+        };
+        room.seats[index] = Some(Seat {
+            name,
+            token: format!("{:016x}", rand::random::<u64>()),
+            sender: Some((connection, sender.clone())),
+        });
+        Ok((room_name, index))
+    }
+
+    /// 席についた人からの知らせを処理する
+    fn handle(&self, room_name: &str, index: usize, message: &Value) -> Result<(), String> {
+        let mut rooms = self.rooms.lock().unwrap();
+        let room = rooms.get_mut(room_name).ok_or("部屋がありません")?;
+        let with = if index == 0 {
+            Pattern::Black
+        } else {
+            Pattern::White
+        };
+        if room.is_over() {
+            return Err("対局は終わっています".to_string());
+        }
+        match message["type"].as_str() {
+            Some("move") => {
+                if room.seats.iter().any(Option::is_none) {
+                    return Err("相手がまだいません".to_string());
+                }
+                if room.game.turn() != with {
+                    return Err("相手の番です".to_string());
+                }
+                let mv: Move = message["move"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .parse()
+                    .map_err(|e| format!("{}", e))?;
+                // パスは自動で入るので、置ける手だけを受けつける
+                match mv.at() {
+                    Some(at) if room.game.board().is_putabele(with, at) => {
+                        room.game.play(mv);
+                        room.game.pass_if_forced();
+                    }
+                    _ => return Err(format!("{} には置けません", mv)),
+                }
+            }
+            Some("resign") => room.resigned = Some(with),
+            _ => return Err("知らない知らせです".to_string()),
+        }
+        room.broadcast(&room.state(room_name));
+        Ok(())
+    }
+
+    /// 接続が切れた。席は合言葉で戻れるように残す
+    fn leave(&self, room_name: &str, index: usize, connection: u64) {
+        let mut rooms = self.rooms.lock().unwrap();
+        let Some(room) = rooms.get_mut(room_name) else {
+            return;
+        };
+        let Some(seat) = room.seats[index].as_mut() else {
+            return;
+        };
+        // 同じ席に戻ってきた新しい接続は残す
+        if seat.sender.as_ref().map(|(id, _)| *id) != Some(connection) {
+            return;
+        }
+        seat.sender = None;
+        room.send(1 - index, &json!({ "type": "opponent_left" }));
+        let empty = room
+            .seats
+            .iter()
+            .all(|seat| seat.as_ref().is_none_or(|seat| seat.sender.is_none()));
+        if empty && (room.is_over() || room.seats.iter().any(Option::is_none)) {
+            rooms.remove(room_name);
+        }
+    }
+}
+
+fn serve(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let mut port: u16 = 9001;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => port = value(&mut args, "--port")?,
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            _ => return Err(format!("{} は知らないオプションです\n{}", arg, USAGE)),
+        }
+    }
+    let address = format!("0.0.0.0:{}", port);
+    let listener = TcpListener::bind(&address)
+        .map_err(|e| format!("{} で待ち受けられません: {}", address, e))?;
+    println!("ws://{} で待ち受けています", address);
+    let lobby = Lobby {
+        rooms: Mutex::new(HashMap::new()),
+        next_id: Mutex::new(0),
+        next_room: Mutex::new(0),
+    };
+    std::thread::scope(|scope| {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else {
+                continue;
+            };
+            let connection = lobby.next_id();
+            let lobby = &lobby;
+            scope.spawn(move || {
+                if let Err(e) = connect(stream, lobby, connection) {
+                    eprintln!("接続 {}: {}", connection, e);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+// 1つの接続を最後まで受け持つ
+fn connect(stream: TcpStream, lobby: &Lobby, connection: u64) -> Result<(), String> {
+    let mut socket = tungstenite::accept(stream).map_err(|e| e.to_string())?;
+    socket
+        .get_ref()
+        .set_read_timeout(Some(POLL))
+        .map_err(|e| e.to_string())?;
+    let (sender, receiver) = mpsc::channel();
+
+    // 席に着くまで待つ
+    let (room_name, index) = loop {
+        let Some(message) = receive(&mut socket)? else {
+            continue;
+        };
+        if message["type"] != "join" {
+            send(
+                &mut socket,
+                &error_message("まず join で部屋に入ってください"),
+            )?;
+            continue;
+        }
+        match lobby.join(&message, connection, &sender) {
+            Ok(seat) => break seat,
+            Err(e) => send(&mut socket, &error_message(&e))?,
+        }
+    };
+    {
+        let rooms = lobby.rooms.lock().unwrap();
+        let room = &rooms[&room_name];
+        let token = &room.seats[index].as_ref().expect("着いた席").token;
+        room.send(
+            index,
+            &json!({
+                "type": "joined",
+                "room": room_name,
+                "color": if index == 0 { "black" } else { "white" },
+                "token": token,
+            }),
+        );
+        room.broadcast(&room.state(&room_name));
+    }
+
+    let result = (|| -> Result<(), String> {
+        loop {
+            if let Some(message) = receive(&mut socket)?
+                && let Err(e) = lobby.handle(&room_name, index, &message)
+            {
+                send(&mut socket, &error_message(&e))?;
+            }
+            for message in receiver.try_iter() {
+                socket
+                    .send(Message::text(message))
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+    })();
+    lobby.leave(&room_name, index, connection);
+    result
+}
+
+fn error_message(message: &str) -> Value {
+    json!({ "type": "error", "message": message })
+}
+
+/// 知らせを1つ読む。`POLL` の間に何も来なければ None。切断されたらエラー
+fn receive(socket: &mut WebSocket<TcpStream>) -> Result<Option<Value>, String> {
+    match socket.read() {
+        Ok(Message::Text(text)) => serde_json::from_str(&text)
+            .map(Some)
+            .map_err(|e| format!("JSON が読めません: {}", e)),
+        Ok(Message::Close(_)) => Err("切断されました".to_string()),
+        Ok(_) => Ok(None),
+        Err(tungstenite::Error::Io(e))
+            if matches!(
+                e.kind(),
+                io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+            ) =>
+        {
+            Ok(None)
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn send(socket: &mut WebSocket<TcpStream>, message: &Value) -> Result<(), String> {
+    socket
+        .send(Message::text(message.to_string()))
+        .map_err(|e| e.to_string())
+}
+
+// 以下クライアント
+
+fn join(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let mut room: Option<String> = None;
+    let mut name = "名無し".to_string();
+    let mut token: Option<String> = None;
+    let mut address: Option<String> = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--room" => room = Some(value(&mut args, "--room")?),
+            "--name" => name = value(&mut args, "--name")?,
+            "--token" => token = Some(value(&mut args, "--token")?),
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            _ if arg.starts_with("--") => {
+                return Err(format!("{} は知らないオプションです\n{}", arg, USAGE));
+            }
+            _ => address = Some(arg),
+        }
+    }
+    let address = address.ok_or(USAGE)?;
+    let input = read_stdin();
+
+    let mut me: Option<Pattern> = None;
+    let mut tries = 0;
+    loop {
+        let mut socket = match open(&address) {
+            Ok(socket) => socket,
+            Err(e) if token.is_some() && tries < RECONNECT_TRIES => {
+                tries += 1;
+                println!("接続できません（{}）。もう一度つなぎます…", e);
+                std::thread::sleep(Duration::from_secs(2));
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        tries = 0;
+        send(
+            &mut socket,
+            &json!({ "type": "join", "room": room, "name": name, "token": token }),
+        )?;
+        match play(&mut socket, &input, &mut me, &mut room, &mut token) {
+            Ok(()) => {
+                socket.close(None).ok();
+                socket.flush().ok();
+                return Ok(());
+            }
+            // 席に着いていれば合言葉で戻る
+            Err(e) if token.is_some() => println!("{}。もう一度つなぎます…", e),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn open(address: &str) -> Result<WebSocket<TcpStream>, String> {
+    let stream = TcpStream::connect(address).map_err(|e| e.to_string())?;
+    let (socket, _) =
+        tungstenite::client(format!("ws://{}/", address), stream).map_err(|e| e.to_string())?;
+    socket
+        .get_ref()
+        .set_read_timeout(Some(POLL))
+        .map_err(|e| e.to_string())?;
+    Ok(socket)
+}
+
+// 標準入力を別のスレッドで読み、1行ずつ渡す
+fn read_stdin() -> Receiver<String> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+/// 対局が終わるか `quit` を入力すると Ok を返す。切断されたらエラー
+fn play(
+    socket: &mut WebSocket<TcpStream>,
+    input: &Receiver<String>,
+    me: &mut Option<Pattern>,
+    room: &mut Option<String>,
+    token: &mut Option<String>,
+) -> Result<(), String> {
+    // この接続で部屋に入れたか。つなぎ直したときは `me` が残っているので別に持つ
+    let mut joined = false;
+    loop {
+        if let Some(message) = receive(socket)? {
+            match message["type"].as_str() {
+                Some("joined") => {
+                    joined = true;
+                    *me = Some(match message["color"].as_str() {
+                        Some("white") => Pattern::White,
+                        _ => Pattern::Black,
+                    });
+                    *room = message["room"].as_str().map(str::to_string);
+                    *token = message["token"].as_str().map(str::to_string);
+                    println!(
+                        "部屋 {} に {} で入りました（合言葉: {}）",
+                        room.as_deref().unwrap_or_default(),
+                        me.unwrap_or_default(),
+                        token.as_deref().unwrap_or_default()
+                    );
+                }
+                // 表示して、対局が終わっていればやめる
+                Some("state") if show_state(&message, *me) => return Ok(()),
+                Some("opponent_left") => println!("相手の接続が切れました。戻るのを待っています"),
+                Some("opponent_returned") => println!("相手が戻ってきました"),
+                // 部屋に入れなければやめる。つなぎ直して合言葉が通らなかったときも同じ
+                Some("error") if !joined => {
+                    *token = None;
+                    return Err(message["message"].as_str().unwrap_or_default().to_string());
+                }
+                Some("error") => println!("⚠️ {}", message["message"].as_str().unwrap_or_default()),
+                _ => {}
+            }
+        }
+        for line in input.try_iter() {
+            let line = line.trim();
+            match line {
+                "" => {}
+                "quit" => return Ok(()),
+                "resign" => send(socket, &json!({ "type": "resign" }))?,
+                mv => send(socket, &json!({ "type": "move", "move": mv }))?,
+            }
+        }
+    }
+}
+
+// 盤面を表示する。対局が終わっていれば true
+fn show_state(message: &Value, me: Option<Pattern>) -> bool {
+    let game = &message["game"];
+    let Ok((osero, turn)) = Osero::parse_position(game["position"].as_str().unwrap_or_default())
+    else {
+        return false;
+    };
+    println!("\n-----------------------------");
+    println!(
+        "黒 X: {}　白 O: {}",
+        message["black"].as_str().unwrap_or("（空席）"),
+        message["white"].as_str().unwrap_or("（空席）")
+    );
+    println!("{}", osero.express());
+    let (black, white, none) = osero.many();
+    println!("黒 X: {}　白 O: {}　空白: {}", black, white, none);
+
+    if let Some(resigned) = message["resigned"].as_str() {
+        let side = if resigned == "black" { "黒" } else { "白" };
+        println!("{}が投了しました。", side);
+        return true;
+    }
+    if game["finished"] == true {
+        match game["result"].as_i64().unwrap_or_default() {
+            0 => println!("引き分けです。"),
+            d if d > 0 => println!("黒の {} 石勝ちです。", d),
+            d => println!("白の {} 石勝ちです。", -d),
+        }
+        return true;
+    }
+    if message["black"].is_null() || message["white"].is_null() {
+        println!("相手を待っています…");
+    } else if Some(turn) == me {
+        println!("あなたの番です。置くマスを入力してください（resign: 投了　quit: 終了）");
+    } else {
+        println!("相手の番です…");
+    }
+    false
+}
@@ -0,0 +1,598 @@
+use std::time::{Duration, Instant};
+
+use osero::{Clock, Engine, Game, Move, MoveNote, Osero, Pattern, Record};
+
+use super::analyze::print_analysis;
+use super::{Ai, ai_for, engine_for, load_book, load_record, save_record, value};
+
+const USAGE: &str =
+    "使い方: osero [--mode モード] [--color 色] [--level N] [--time ミリ秒] [--clock 秒 [--increment 秒]]
+             [--ai AI] [--book ファイル [--book-random N]] [--position 局面]
+  --mode モード    human-ai（人と AI、既定）、human-human（人どうし）、ai-ai（AI どうし）。
+                   省くと --level も --time もなければ最初に選ぶ
+  --color 色       human-ai で人が持つ色。black（既定）か white
+  --level N        AI のレベル（読む深さ。MCTS ではプレイアウト回数 N × 2000）
+  --black-level N  黒の AI のレベル（--white-level も同じ）
+  --black-ai AI    黒の AI（--white-ai も同じ）。省くと --ai の AI
+  --time ミリ秒    AI が1手に考える時間
+  --clock 秒       お互いの持ち時間（使い切ったら負け）
+  --increment 秒   1手ごとに加える時間（フィッシャールール）
+  --ai AI          standard（既定）、positional、重みのファイル（以上はαβ探索の評価関数）、
+                   mcts（モンテカルロ木探索）、mcts-random（ランダムなプレイアウトの MCTS）
+  --book ファイル  定石のファイル（例: books/openings.txt）。定石にある局面では AI が定石を打つ
+  --book-random N  最善の定石から1局あたり N 石差以内の手まで候補にする（既定 0）
+  --position 局面  この局面から始める。a1〜h8 の順に 64 マスを X・O・- で書き、最後に手番
+                   （例: ---------------------------OX------XO--------------------------- X）
+
+  osero tui ...      全画面で対局する。オプションは上と同じ（レベルの既定は 4）
+  osero analyze ...  局面の合法手をすべて読んで比べる（osero analyze --help）
+  osero compare ...  AI どうしを対局させて比べる（osero compare --help）
+  osero train ...    自己対局で評価関数の重みを学習する（osero train --help）
+  osero book ...     自己対局で定石を学習する（osero book --help）
+  osero wthor ...    WTHOR の対局を読んで集計する（osero wthor --help）
+  osero serve ...    HTTP/JSON の API を開く（osero serve --help）
+  osero online ...   LAN の中で人どうしが対局する（osero online --help）
+  osero --nboard ... NBoard のエンジンとして動く（osero --nboard --help）
+  osero --gtp ...    GTP に似たコマンドでやりとりする（osero --gtp --help）";
+
+/// だれとだれが打つか
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Mode {
+    HumanAi,
+    HumanHuman,
+    AiAi,
+}
+
+impl std::str::FromStr for Mode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human-ai" => Ok(Mode::HumanAi),
+            "human-human" => Ok(Mode::HumanHuman),
+            "ai-ai" => Ok(Mode::AiAi),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Mode {
+    /// [黒, 白] のどちらを人が打つか。`color` は human-ai で人が持つ色
+    pub fn humans(self, color: Pattern) -> [bool; 2] {
+        match self {
+            Mode::HumanAi => [color == Pattern::Black, color == Pattern::White],
+            Mode::HumanHuman => [true, true],
+            Mode::AiAi => [false, false],
+        }
+    }
+}
+
+/// 黒か白の一方を打つ側
+pub enum Player {
+    Human,
+    Ai { ai: Ai, name: String, level: usize },
+}
+
+impl Player {
+    pub fn is_human(&self) -> bool {
+        matches!(self, Player::Human)
+    }
+
+    /// 棋譜に書く名前
+    pub fn name(&self) -> &str {
+        match self {
+            Player::Human => "Human",
+            Player::Ai { name, .. } => name,
+        }
+    }
+}
+
+// [黒, 白] の添字
+pub fn side(with: Pattern) -> usize {
+    if with == Pattern::White { 1 } else { 0 }
+}
+
+pub fn color_name(with: Pattern) -> &'static str {
+    if with == Pattern::White { "白" } else { "黒" }
+}
+
+pub struct Options {
+    pub mode: Option<Mode>,
+    /// human-ai で人が持つ色
+    pub color: Pattern,
+    pub level: Option<usize>,
+    /// [黒, 白] の AI のレベルと名前
+    pub levels: [Option<usize>; 2],
+    pub ais: [Option<String>; 2],
+    pub move_time: Option<Duration>,
+    pub clock: Option<Duration>,
+    pub increment: Duration,
+    pub ai: String,
+    pub book: Option<String>,
+    pub book_randomness: u32,
+    pub position: Option<(Osero, Pattern)>,
+}
+
+pub fn parse_options(args: Vec<String>) -> Result<Options, String> {
+    let mut options = Options {
+        mode: None,
+        color: Pattern::Black,
+        level: None,
+        levels: [None, None],
+        ais: [None, None],
+        move_time: None,
+        clock: None,
+        increment: Duration::ZERO,
+        ai: "standard".to_string(),
+        book: None,
+        book_randomness: 0,
+        position: None,
+    };
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--mode" => options.mode = Some(value(&mut args, "--mode")?),
+            "--color" => {
+                options.color = match value::<String>(&mut args, "--color")?.as_str() {
+                    "black" => Pattern::Black,
+                    "white" => Pattern::White,
+                    color => return Err(format!("--color の値 {} は無効です", color)),
+                }
+            }
+            "--level" => options.level = Some(value(&mut args, "--level")?),
+            "--black-level" => options.levels[0] = Some(value(&mut args, &arg)?),
+            "--white-level" => options.levels[1] = Some(value(&mut args, &arg)?),
+            "--black-ai" => options.ais[0] = Some(value(&mut args, &arg)?),
+            "--white-ai" => options.ais[1] = Some(value(&mut args, &arg)?),
+            "--time" => {
+                options.move_time = Some(Duration::from_millis(value(&mut args, "--time")?))
+            }
+            "--clock" => options.clock = Some(Duration::from_secs(value(&mut args, "--clock")?)),
+            "--increment" => {
+                options.increment = Duration::from_secs(value(&mut args, "--increment")?)
+            }
+            "--ai" | "--eval" => options.ai = value(&mut args, &arg)?,
+            "--book" => options.book = Some(value(&mut args, "--book")?),
+            "--book-random" => options.book_randomness = value(&mut args, "--book-random")?,
+            "--position" => {
+                let position: String = value(&mut args, "--position")?;
+                options.position =
+                    Some(Osero::parse_position(&position).map_err(|e| e.to_string())?);
+            }
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            _ => return Err(format!("{} は知らないオプションです\n{}", arg, USAGE)),
+        }
+    }
+    Ok(options)
+}
+
+pub fn run(args: Vec<String>) -> Result<(), String> {
+    let options = parse_options(args)?;
+
+    let mut game = match options.position {
+        Some((osero, turn)) => Game::from_position(osero, turn),
+        None => Game::new(),
+    };
+    // [黒, 白] の持ち時間
+    let mut clocks = options
+        .clock
+        .map(|total| [Clock::fischer(total, options.increment); 2]);
+    let mut turn_start: Option<Instant> = None;
+    // 手ごとの AI の評価値と時間。GGF で保存するときに添える
+    let mut notes: Vec<MoveNote> = vec![];
+    let mut time_loser: Option<Pattern> = None;
+
+    let timed = options.move_time.is_some() || clocks.is_some();
+    let interactive = options.level.is_none() && !timed;
+    let (mode, color) = match options.mode {
+        Some(mode) => (mode, options.color),
+        None if interactive => match choose_mode() {
+            Some(chosen) => chosen,
+            None => {
+                println!("入力が終わったので終了します。");
+                return Ok(());
+            }
+        },
+        None => (Mode::HumanAi, options.color),
+    };
+    let humans = mode.humans(color);
+
+    let mut level = options.level.unwrap_or(1);
+    // レベルを決めていない AI があれば選んでもらう
+    let unset = humans
+        .iter()
+        .zip(options.levels)
+        .any(|(&human, level)| !human && level.is_none());
+    if interactive && unset {
+        loop {
+            println!("レベルを選んでください");
+            let mut level_str = String::new();
+            if std::io::stdin().read_line(&mut level_str).unwrap_or(0) == 0 {
+                println!("入力が終わったので終了します。");
+                return Ok(());
+            }
+            if level_str.trim().is_empty() {
+                println!("{}は無効です。", level_str);
+                println!("例: 1");
+            } else {
+                if let Ok(num) = level_str.trim().parse::<usize>() {
+                    level = num;
+                    break;
+                } else {
+                    println!("{}は無効です。", level_str);
+                    println!("例: 1");
+                }
+            }
+        }
+    }
+
+    let players = players(&options, humans, level)?;
+    // hint と analyze で使う。人が打つなら --ai をここで読んで確かめておく
+    let hint_engine = if humans.contains(&true) {
+        Some(hint_engine_for(&options.ai)?)
+    } else {
+        None
+    };
+
+    println!("オセロ対決スタートです！");
+    println!("黒（Black）が先手です。\n");
+    for (with, player) in [Pattern::Black, Pattern::White].into_iter().zip(&players) {
+        match player {
+            Player::Human => println!("{}: 人", color_name(with)),
+            Player::Ai { name, level, .. } if !timed => {
+                println!("{}: AI（{}、レベル {}）", color_name(with), name, level)
+            }
+            Player::Ai { name, .. } => println!("{}: AI（{}）", color_name(with), name),
+        }
+    }
+    if let Some([clock, _]) = clocks {
+        println!(
+            "持ち時間: {}秒（1手ごとに +{}秒）",
+            clock.remaining().as_secs(),
+            clock.increment().as_secs()
+        );
+    }
+
+    loop {
+        if game.is_finished() {
+            break;
+        }
+        let with = game.turn();
+        let osero = *game.board();
+
+        if game.pass_if_forced() {
+            println!("置けないので強制的にパスします。");
+            continue;
+        }
+
+        // AI の番
+        if let Player::Ai { ai, level, .. } = &players[side(with)] {
+            let start = Instant::now();
+            let (_, _, empties) = osero.many();
+            let move_time = match clocks {
+                Some(clocks) => Some(clocks[side(with)].budget(empties)),
+                None => options.move_time,
+            };
+            let (best, note) = ai.think_with_note(&osero, with, *level, move_time);
+            note_move(&mut notes, &game, note);
+            if let Some(clocks) = clocks.as_mut()
+                && !clocks[side(with)].spend(start.elapsed())
+            {
+                time_loser = Some(with);
+                break;
+            }
+            if let Some(pos) = best {
+                println!("\n-----------------------------");
+                println!("😼『うにゃっ、そこがよさそうだにゃ……』");
+                println!(
+                    "AI（{}）は {} に置いたにゃ〜",
+                    color_name(with),
+                    Move::from(pos)
+                );
+                game.play(Move::from(pos));
+
+                // ここで盤面を表示！
+                let osero = game.board();
+                println!("{}", osero.express());
+                let (black, white, none) = osero.many();
+                println!("黒 X: {}　白 O: {}　空白: {}", black, white, none);
+                print_clocks(&clocks);
+            } else {
+                println!("😿『置けないにゃ…パスするにゃ』");
+                game.play(Move::Pass);
+            }
+            continue;
+        }
+
+        // 人の番
+        println!("\n-----------------------------");
+        println!("{}", osero.express());
+        let (black, white, none) = osero.many();
+        println!("黒 X: {}　白 O: {}　空白: {}", black, white, none);
+        print_clocks(&clocks);
+        let opening = players.iter().find_map(|player| match player {
+            Player::Ai { ai, .. } => ai.opening_name(&osero, with),
+            Player::Human => None,
+        });
+        if let Some(name) = opening {
+            println!("定石: {}", name);
+        }
+        println!(
+            "{} のターンです。置くマスを入力してください（例: `{}`）",
+            with,
+            osero.legal_moves(with)[0]
+        );
+        println!(
+            "u: 待った　r: やり直し　save ファイル: 保存　load ファイル: 続きから（.ggf なら GGF）"
+        );
+        println!(
+            "position: 局面を表示　setup 局面: その局面から始める　hint [N]: ヒント（N 手先まで読む）　analyze [N]: すべての手を比べる"
+        );
+
+        // 入力をやり直しても時計は止めない
+        let start = *turn_start.get_or_insert_with(Instant::now);
+        let mut input = String::new();
+        if std::io::stdin().read_line(&mut input).unwrap_or(0) == 0 {
+            println!("入力が終わったので終了します。");
+            return Ok(());
+        }
+
+        match input.trim() {
+            "" => println!("⚠️ 入力が空です。もう一度入力してください。"),
+            "u" => {
+                // AI の手と自分の手をまとめて戻し、また人が打てる番にする
+                let mut undone = vec![];
+                while let Some(mv) = game.undo() {
+                    undone.push(mv);
+                    if human_can_move(&game, &players) {
+                        break;
+                    }
+                }
+                if undone.is_empty() {
+                    println!("戻せる手がありません。");
+                } else {
+                    println!("{} 手戻しました。", undone.len());
+                }
+            }
+            "r" => {
+                let mut redone = vec![];
+                while let Some(mv) = game.redo() {
+                    redone.push(mv);
+                    if human_can_move(&game, &players) {
+                        break;
+                    }
+                }
+                if redone.is_empty() {
+                    println!("やり直せる手がありません。");
+                } else {
+                    println!("{} 手やり直しました。", redone.len());
+                }
+            }
+            "position" => println!("{}", osero.position_string(with)),
+            input
+                if input == "hint"
+                    || input.starts_with("hint ")
+                    || input == "analyze"
+                    || input.starts_with("analyze ") =>
+            {
+                let (command, depth) = input.split_once(' ').unwrap_or((input, ""));
+                let depth = match depth.trim() {
+                    "" => None,
+                    depth => match depth.parse::<usize>() {
+                        Ok(depth) => Some(depth),
+                        Err(_) => {
+                            println!("⚠️ 読む深さ {} が無効です。", depth);
+                            continue;
+                        }
+                    },
+                };
+                let Some(engine) = &hint_engine else {
+                    println!("⚠️ ヒントを出す AI がありません。");
+                    continue;
+                };
+                if command == "hint" {
+                    print_hint(engine, &osero, with, depth, options.move_time);
+                } else {
+                    print_analysis(engine, &osero, with, depth, options.move_time, true);
+                }
+            }
+            input if input.starts_with("setup ") => {
+                match Osero::parse_position(&input["setup ".len()..]) {
+                    Ok((osero, turn)) => {
+                        game = Game::from_position(osero, turn);
+                        notes.clear();
+                        turn_start = None;
+                        println!("局面を置き換えました。");
+                    }
+                    Err(e) => println!("⚠️ {}", e),
+                }
+            }
+            input if input.starts_with("save ") => {
+                let path = input["save ".len()..].trim();
+                let level = players.iter().find_map(|player| match player {
+                    Player::Ai { level, .. } if !timed => Some(*level),
+                    _ => None,
+                });
+                let black = players[0].name();
+                let white = players[1].name();
+                let record = Record::new(game.clone(), black, white, level);
+                match save_record(record, &notes, path) {
+                    Ok(()) => println!("{} に保存しました。", path),
+                    Err(e) => println!("⚠️ {} に保存できません: {}", path, e),
+                }
+            }
+            input if input.starts_with("load ") => {
+                let path = input["load ".len()..].trim();
+                match load_record(path) {
+                    Ok(record) => {
+                        game = record.game;
+                        notes.clear();
+                        turn_start = None;
+                        println!(
+                            "{} を読み込みました（{} 手目から）。",
+                            path,
+                            game.moves().len() + 1
+                        );
+                    }
+                    Err(e) => println!("⚠️ {} を読み込めません: {}", path, e),
+                }
+            }
+            input => match input.parse::<Move>() {
+                Ok(Move::Pass) => {
+                    println!("置ける場所があるのでパスはできません。");
+                }
+                Ok(mv) if osero.is_legal(with, mv) => {
+                    turn_start = None;
+                    if let Some(clocks) = clocks.as_mut()
+                        && !clocks[side(with)].spend(start.elapsed())
+                    {
+                        time_loser = Some(with);
+                        break;
+                    }
+                    note_move(&mut notes, &game, MoveNote::default());
+                    game.play(mv);
+                }
+                Ok(mv) => {
+                    println!("{} には置けません。もう一度入力してください。", mv);
+                }
+                Err(e) => println!("⚠️ {}", e),
+            },
+        }
+    }
+
+    println!("\n=============================");
+    println!("ゲーム終了です。結果を発表します！");
+
+    let osero = game.board();
+    let (black, white, _) = osero.many();
+    println!("● Black: {}, ○ White: {}", black, white);
+
+    if let Some(loser) = time_loser {
+        println!("⌛ {} の時間切れです。", loser);
+    }
+    if let Some(winner) = time_loser.map(|loser| loser.fliped()).or(osero.which_win()) {
+        let loser = &players[side(winner.fliped())];
+        match &players[side(winner)] {
+            Player::Human if !loser.is_human() => println!(
+                "🎉 {}（{}）の勝ちです！おめでとうございます！",
+                color_name(winner),
+                winner
+            ),
+            Player::Ai { .. } if loser.is_human() => {
+                println!(
+                    "😼 {}（AI）の勝ちだにゃ〜！やったにゃ〜！",
+                    color_name(winner)
+                )
+            }
+            _ => println!("🏆 {}（{}）の勝ちです！", color_name(winner), winner),
+        }
+    } else {
+        println!("🤝 引き分けです。再挑戦してみてください！");
+    }
+    println!("=============================\n");
+    Ok(())
+}
+
+/// [黒, 白] を打つ側を作る。AI のレベルは黒と白のそれぞれに指定がなければ `level`
+pub fn players(options: &Options, humans: [bool; 2], level: usize) -> Result<Vec<Player>, String> {
+    let book = match &options.book {
+        Some(path) => Some(load_book(path, options.book_randomness)?),
+        None => None,
+    };
+    let mut players = vec![];
+    for (i, &human) in humans.iter().enumerate() {
+        if human {
+            players.push(Player::Human);
+            continue;
+        }
+        let name = options.ais[i].clone().unwrap_or_else(|| options.ai.clone());
+        let mut ai = ai_for(&name)?;
+        if let Some(book) = &book {
+            ai = ai.with_book(book.clone())?;
+        }
+        let level = options.levels[i].unwrap_or(level);
+        players.push(Player::Ai { ai, name, level });
+    }
+    Ok(players)
+}
+
+// これから打つ手の評価値と時間を覚える。待ったのあとに打ち直したなら、その先の分は捨てる
+fn note_move(notes: &mut Vec<MoveNote>, game: &Game, note: MoveNote) {
+    notes.resize(game.moves().len(), MoveNote::default());
+    notes.push(note);
+}
+
+/// hint で深さを指定しなければこの深さまで読む
+const HINT_DEPTH: usize = 8;
+
+// ヒントは αβ探索で読む。MCTS で遊んでいるときは standard で読む
+fn hint_engine_for(ai: &str) -> Result<Engine, String> {
+    if ai.starts_with("mcts") {
+        engine_for("standard")
+    } else {
+        engine_for(ai)
+    }
+}
+
+// 盤面は変えずに、最善手と評価値、読み筋を表示する。深さを指定しなければ --time があればその時間だけ読む
+fn print_hint(
+    engine: &Engine,
+    osero: &Osero,
+    with: Pattern,
+    depth: Option<usize>,
+    move_time: Option<Duration>,
+) {
+    let result = match (depth, move_time) {
+        (None, Some(limit)) => engine.search_timed(osero, with, limit),
+        (depth, _) => engine.search(osero, with, depth.unwrap_or(HINT_DEPTH)),
+    };
+    let result = engine.with_pv(osero, with, result);
+    let score = match result.disc_difference() {
+        Some(discs) => format!("読み切りで {:+} 石", discs),
+        None => format!(
+            "{:+.1} 石（{} 手先まで）",
+            engine.discs(&result),
+            result.depth
+        ),
+    };
+    println!("💡 ヒント: {}　評価: {}", result.best(), score);
+    let pv: Vec<String> = result.pv.iter().map(Move::to_string).collect();
+    println!("   読み筋: {}", pv.join(" "));
+}
+
+/// 人の番で、パス以外の手が打てるか
+pub fn human_can_move(game: &Game, players: &[Player]) -> bool {
+    players[side(game.turn())].is_human() && game.legal_moves() != [Move::Pass]
+}
+
+// 最初にモードと人の色を選ぶ。入力が終わったら None
+fn choose_mode() -> Option<(Mode, Pattern)> {
+    loop {
+        println!("モードを選んでください");
+        println!("1: 人（黒） vs AI　2: 人（白） vs AI　3: 人 vs 人　4: AI vs AI");
+        let mut input = String::new();
+        if std::io::stdin().read_line(&mut input).unwrap_or(0) == 0 {
+            return None;
+        }
+        match input.trim() {
+            "1" => return Some((Mode::HumanAi, Pattern::Black)),
+            "2" => return Some((Mode::HumanAi, Pattern::White)),
+            "3" => return Some((Mode::HumanHuman, Pattern::Black)),
+            "4" => return Some((Mode::AiAi, Pattern::Black)),
+            input => {
+                println!("{}は無効です。", input);
+                println!("例: 1");
+            }
+        }
+    }
+}
+
+fn print_clocks(clocks: &Option<[Clock; 2]>) {
+    if let Some([black, white]) = clocks {
+        println!(
+            "残り時間 黒: {:.1}秒　白: {:.1}秒",
+            black.remaining().as_secs_f64(),
+            white.remaining().as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use osero::{Game, Move, Osero, Pattern};
+use serde_json::{Value, json};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use super::{Ai, ai_for, value};
+
+const USAGE: &str =
+    "使い方: osero serve [--port N] [--threads N] [--level N] [--ai AI] [--max-games N]
+  localhost で HTTP/JSON の API を開く。対局は同時にいくつもメモリに持つ
+  --port N       待ち受けるポート（既定 8080）
+  --threads N    同時に処理するリクエストの数（既定 4）
+  --level N      AI に考えさせるときのレベルの既定値（既定 4）
+  --ai AI        対局を作るときに AI を指定しなかった場合の AI（既定 standard）
+  --max-games N  同時に持つ対局の数の上限（既定 100）。DELETE で消すと空く
+
+  POST   /games            対局を作る       {\"position\": 局面, \"ai\": AI}（どちらも省略可）
+  GET    /games            対局の一覧
+  GET    /games/ID         盤面と手番、合法手、石の数
+  DELETE /games/ID         対局を消す
+  GET    /games/ID/moves   合法手
+  POST   /games/ID/moves   手を打つ         {\"move\": \"f5\"}
+  POST   /games/ID/ai      AI に打たせる    {\"level\": 1〜12} か {\"time_ms\": 1〜60000}";
+
+/// リクエストで AI を指定するときに使える名前。重みのファイルは --ai でしか指定できない
+const AI_NAMES: [&str; 4] = ["standard", "positional", "mcts", "mcts-random"];
+
+/// リクエストで指定できるレベルと考える時間の上限。大きすぎると対局をいつまでもロックする。
+/// レベル 12 は中盤の局面でも1コアで数秒、1つ上げるごとに数倍かかる
+const MAX_LEVEL: usize = 12;
+const MAX_TIME_MS: u64 = 60_000;
+
+struct ServedGame {
+    game: Game,
+    ai: Arc<Ai>,
+}
+
+struct Api {
+    games: Mutex<HashMap<u64, Arc<Mutex<ServedGame>>>>,
+    next_id: Mutex<u64>,
+    max_games: usize,
+    level: usize,
+    ai: String,
+    // AI の名前ごとに1つ作って対局の間で使い回す。置換表を対局ごとに持つと対局の数だけメモリを使う
+    ais: Mutex<HashMap<String, Arc<Ai>>>,
+}
+
+/// 返事のステータスコードと JSON
+type Reply = (u16, Value);
+
+fn error(status: u16, message: impl Into<String>) -> Reply {
+    (status, json!({ "error": message.into() }))
+}
+
+impl Api {
+    fn handle(&self, method: &Method, path: &str, body: &str) -> Reply {
+        let body: Value = if body.trim().is_empty() {
+            json!({})
+        } else {
+            match serde_json::from_str(body) {
+                Ok(body) => body,
+                Err(e) => return error(400, format!("JSON が読めません: {}", e)),
+            }
+        };
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        match (method, segments.as_slice()) {
+            (Method::Post, ["games"]) => self.create(&body),
+            (Method::Get, ["games"]) => {
+                let mut ids: Vec<u64> = self.games.lock().unwrap().keys().copied().collect();
+                ids.sort();
+                (200, json!({ "games": ids }))
+            }
+            (method, ["games", id, rest @ ..]) => {
+                let Ok(id) = id.parse::<u64>() else {
+                    return error(404, format!("対局 {} はありません", id));
+                };
+                if *method == Method::Delete && rest.is_empty() {
+                    return match self.games.lock().unwrap().remove(&id) {
+                        Some(_) => (200, json!({ "deleted": id })),
+                        None => error(404, format!("対局 {} はありません", id)),
+                    };
+                }
+                let Some(served) = self.games.lock().unwrap().get(&id).cloned() else {
+                    return error(404, format!("対局 {} はありません", id));
+                };
+                // 対局ごとにロックするので、AI が考えている間も別の対局は進められる
+                let mut served = served.lock().unwrap();
+                match (method, rest) {
+                    (Method::Get, []) => (200, served_json(id, &served.game)),
+                    (Method::Get, ["moves"]) => {
+                        (200, json!({ "moves": legal_moves(&served.game) }))
+                    }
+                    (Method::Post, ["moves"]) => play(id, &mut served.game, &body),
+                    (Method::Post, ["ai"]) => self.think(id, &mut served, &body),
+                    _ => error(404, format!("{} {} はありません", method, path)),
+                }
+            }
+            _ => error(404, format!("{} {} はありません", method, path)),
+        }
+    }
+
+    fn create(&self, body: &Value) -> Reply {
+        let mut game = match body["position"].as_str() {
+            Some(position) => match Osero::parse_position(position) {
+                Ok((osero, turn)) => Game::from_position(osero, turn),
+                Err(e) => return error(400, e.to_string()),
+            },
+            None => Game::new(),
+        };
+        let ai = match body["ai"].as_str() {
+            Some(name) if !AI_NAMES.contains(&name) => {
+                return error(400, format!("AI は {} のどれかです", AI_NAMES.join("、")));
+            }
+            Some(name) => self.shared_ai(name),
+            None => self.shared_ai(&self.ai),
+        };
+        let ai = match ai {
+            Ok(ai) => ai,
+            Err(e) => return error(500, e),
+        };
+        // API からはパスの局面が見えないよう、パスするしかなければパスしておく
+        game.pass_if_forced();
+        let mut games = self.games.lock().unwrap();
+        if games.len() >= self.max_games {
+            return error(
+                503,
+                format!(
+                    "対局は {} までです。終わった対局を消してください",
+                    self.max_games
+                ),
+            );
+        }
+        let id = {
+            let mut next_id = self.next_id.lock().unwrap();
+            *next_id += 1;
+            *next_id
+        };
+        let json = served_json(id, &game);
+        games.insert(id, Arc::new(Mutex::new(ServedGame { game, ai })));
+        (201, json)
+    }
+
+    fn shared_ai(&self, name: &str) -> Result<Arc<Ai>, String> {
+        let mut ais = self.ais.lock().unwrap();
+        if let Some(ai) = ais.get(name) {
+            return Ok(ai.clone());
+        }
+        let ai = Arc::new(ai_for(name)?);
+        ais.insert(name.to_string(), ai.clone());
+        Ok(ai)
+    }
+
+    fn think(&self, id: u64, served: &mut ServedGame, body: &Value) -> Reply {
+        if served.game.is_finished() {
+            return error(409, "終局しています");
+        }
+        let level = match &body["level"] {
+            Value::Null => self.level,
+            level => match level.as_u64() {
+                Some(level) if (1..=MAX_LEVEL as u64).contains(&level) => level as usize,
+                _ => return error(400, format!("level は 1〜{} です", MAX_LEVEL)),
+            },
+        };
+        let move_time = match &body["time_ms"] {
+            Value::Null => None,
+            time => match time.as_u64() {
+                Some(ms) if (1..=MAX_TIME_MS).contains(&ms) => Some(Duration::from_millis(ms)),
+                _ => return error(400, format!("time_ms は 1〜{} です", MAX_TIME_MS)),
+            },
+        };
+        let osero = *served.game.board();
+        let with = served.game.turn();
+        let mv = Move::from_at(served.ai.think(&osero, with, level, move_time));
+        served.game.play(mv);
+        served.game.pass_if_forced();
+        (
+            200,
+            json!({ "move": mv.to_string(), "game": served_json(id, &served.game) }),
+        )
+    }
+}
+
+fn play(id: u64, game: &mut Game, body: &Value) -> Reply {
+    let Some(mv) = body["move"].as_str() else {
+        return error(400, "move がありません");
+    };
+    let mv: Move = match mv.parse() {
+        Ok(mv) => mv,
+        Err(e) => return error(400, format!("{}", e)),
+    };
+    if !game.play(mv) {
+        return error(409, format!("{} は打てません", mv));
+    }
+    game.pass_if_forced();
+    (200, served_json(id, game))
+}
+
+fn legal_moves(game: &Game) -> Vec<String> {
+    game.board()
+        .get_moveable_points(game.turn())
+        .into_iter()
+        .map(|at| Move::from(at).to_string())
+        .collect()
+}
+
+pub fn color(pattern: Pattern) -> &'static str {
+    match pattern {
+        Pattern::Black => "black",
+        Pattern::White => "white",
+        Pattern::None => "none",
+    }
+}
+
+/// 対局の番号をつけた `game_json`
+fn served_json(id: u64, game: &Game) -> Value {
+    let mut json = game_json(game);
+    json["id"] = id.into();
+    json
+}
+
+/// 盤面と手番、合法手、石の数などを JSON にする
+pub fn game_json(game: &Game) -> Value {
+    let osero = game.board();
+    let cells = osero.to_string();
+    let rows: Vec<&str> = (0..8).map(|y| &cells[y * 8..y * 8 + 8]).collect();
+    let (black, white, empty) = osero.many();
+    let finished = game.is_finished();
+    json!({
+        "board": rows,
+        "position": osero.position_string(game.turn()),
+        "turn": if finished { Value::Null } else { color(game.turn()).into() },
+        "moves": game.transcript(),
+        "legal_moves": legal_moves(game),
+        "discs": { "black": black, "white": white, "empty": empty },
+        "finished": finished,
+        "result": game.result(),
+    })
+}
+
+fn respond(mut request: Request, api: &Api) {
+    let mut body = String::new();
+    let (status, json) = match request.as_reader().read_to_string(&mut body) {
+        Ok(_) => {
+            let path = request
+                .url()
+                .split('?')
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            api.handle(request.method(), &path, &body)
+        }
+        Err(e) => error(400, format!("本文が読めません: {}", e)),
+    };
+    let header = Header::from_bytes("Content-Type", "application/json; charset=utf-8")
+        .expect("正しいヘッダー");
+    let response = Response::from_string(json.to_string())
+        .with_status_code(status)
+        .with_header(header);
+    if let Err(e) = request.respond(response) {
+        eprintln!("返事を送れません: {}", e);
+    }
+}
+
+pub fn run(args: Vec<String>) -> Result<(), String> {
+    let mut port: u16 = 8080;
+    let mut threads: usize = 4;
+    let mut level = 4;
+    let mut ai = "standard".to_string();
+    let mut max_games = 100;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => port = value(&mut args, "--port")?,
+            "--threads" => threads = value(&mut args, "--threads")?,
+            "--level" => level = value(&mut args, "--level")?,
+            "--ai" | "--eval" => ai = value(&mut args, &arg)?,
+            "--max-games" => max_games = value(&mut args, "--max-games")?,
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            _ => return Err(format!("{} は知らないオプションです\n{}", arg, USAGE)),
+        }
+    }
+    if !(1..=MAX_LEVEL).contains(&level) {
+        return Err(format!("--level は 1〜{} です", MAX_LEVEL));
+    }
+    // 重みのファイルが読めるかを先に確かめる
+    let default_ai = Arc::new(ai_for(&ai)?);
+
+    let address = format!("127.0.0.1:{}", port);
+    let http =
+        Server::http(&address).map_err(|e| format!("{} で待ち受けられません: {}", address, e))?;
+    let api = Api {
+        games: Mutex::new(HashMap::new()),
+        next_id: Mutex::new(0),
+        max_games,
+        level,
+        ais: Mutex::new(HashMap::from([(ai.clone(), default_ai)])),
+        ai,
+    };
+    println!("http://{} で待ち受けています", address);
+    std::thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            scope.spawn(|| {
+                while let Ok(request) = http.recv() {
+                    respond(request, &api);
+                }
+            });
+        }
+    });
+    Ok(())
+}
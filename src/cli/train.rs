@@ -0,0 +1,82 @@
+use osero::{FitOptions, PatternEvaluator, fit, read_samples, self_play, write_samples};
+
+use super::{engine_for, value};
+
+const USAGE: &str = "使い方: osero train --output ファイル [オプション]
+  自己対局で局面を集め、最終的な石差を予測するパターンの重みを学習して書き出す
+  --output ファイル      学習した重みの書き出し先（必須）
+  --games N              自己対局の数（既定 100）
+  --level N              自己対局で読む深さ（既定 4）
+  --random N             最初の N 手はランダムに打つ（既定 8）
+  --seed N               乱数の種。同じ種なら同じ対局になる（既定 0）
+  --eval 評価関数        自己対局に使う評価関数（既定 standard）
+  --samples ファイル     自己対局の代わりに保存しておいた局面を読む
+  --save-samples ファイル 集めた局面を保存する
+  --epochs N             学習の回数（既定 200）
+  --rate 数値            学習率（既定 1.0）
+  --phases N             序盤〜終盤を何段階に分けて重みを持つか（1〜60、既定 6）";
+
+pub fn run(args: Vec<String>) -> Result<(), String> {
+    let mut output: Option<String> = None;
+    let mut games = 100;
+    let mut level = 4;
+    let mut random = 8;
+    let mut seed = 0;
+    let mut eval = "standard".to_string();
+    let mut samples_path: Option<String> = None;
+    let mut save_samples: Option<String> = None;
+    let mut options = FitOptions::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" | "-o" => output = Some(value(&mut args, "--output")?),
+            "--games" => games = value(&mut args, "--games")?,
+            "--level" => level = value(&mut args, "--level")?,
+            "--random" => random = value(&mut args, "--random")?,
+            "--seed" => seed = value(&mut args, "--seed")?,
+            "--eval" => eval = value(&mut args, "--eval")?,
+            "--samples" => samples_path = Some(value(&mut args, "--samples")?),
+            "--save-samples" => save_samples = Some(value(&mut args, "--save-samples")?),
+            "--epochs" => options.epochs = value(&mut args, "--epochs")?,
+            "--rate" => options.learning_rate = value(&mut args, "--rate")?,
+            "--phases" => options.phases = value(&mut args, "--phases")?,
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            _ => return Err(format!("{} は知らないオプションです\n{}", arg, USAGE)),
+        }
+    }
+    let output = output.ok_or(USAGE.to_string())?;
+    if !(1..=PatternEvaluator::MAX_PHASES).contains(&options.phases) {
+        return Err(format!(
+            "--phases は 1〜{} です",
+            PatternEvaluator::MAX_PHASES
+        ));
+    }
+
+    let samples = match samples_path {
+        Some(path) => read_samples(&path)
+            .map_err(|e| format!("局面のファイル {} を読めません: {}", path, e))?,
+        None => {
+            // 評価関数が読めるかを先に確かめておく
+            engine_for(&eval)?;
+            println!("自己対局を {} 局打ちます（深さ {}）", games, level);
+            let engine = || engine_for(&eval).unwrap_or_default();
+            self_play(engine, games, level, random, seed)
+        }
+    };
+    println!("{} 局面を集めました", samples.len());
+    if let Some(path) = save_samples {
+        write_samples(&path, &samples)
+            .map_err(|e| format!("局面を {} に保存できません: {}", path, e))?;
+    }
+
+    let evaluator = fit(&samples, options, |epoch, error| {
+        if epoch == 1 || epoch % 10 == 0 || epoch == options.epochs {
+            println!("{:>4} 回目: 平均二乗誤差 {:.2}", epoch, error);
+        }
+    });
+    evaluator
+        .save(&output)
+        .map_err(|e| format!("重みを {} に保存できません: {}", output, e))?;
+    println!("重みを {} に書き出しました", output);
+    Ok(())
+}
@@ -0,0 +1,326 @@
+use std::io;
+use std::time::Duration;
+
+use crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, MouseButton,
+    MouseEventKind,
+};
+use osero::{Game, Move, Pattern};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use super::play::{self, Player, color_name, human_can_move, side};
+
+/// レベルを指定しなければこのレベルで考える
+const DEFAULT_LEVEL: usize = 4;
+/// 盤の左の行番号の幅と、1マスの幅
+const LABEL_WIDTH: u16 = 2;
+const CELL_WIDTH: u16 = 3;
+
+/// 置いたマスと、それで返った石
+type LastMove = ((usize, usize), Vec<(usize, usize)>);
+
+struct App {
+    game: Game,
+    players: Vec<Player>,
+    move_time: Option<Duration>,
+    cursor: (usize, usize),
+    message: String,
+    // 最後に描いた盤の内側。クリックしたマスを求めるのに使う
+    board_area: Rect,
+}
+
+impl App {
+    /// 最後に置いた手と、その手で返った石
+    fn last_move(&self) -> Option<LastMove> {
+        let at = self.game.moves().iter().rev().find_map(Move::at)?;
+        let (before, with) = *self.game.positions().last()?;
+        let mut after = before;
+        after.put(with, at);
+        let flipped = (0..64)
+            .map(|i| (i % 8, i / 8))
+            .filter(|&cell| cell != at && before.get(cell) != after.get(cell))
+            .collect();
+        Some((at, flipped))
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(11), Constraint::Length(4)]).areas(frame.area());
+        let [board, moves] = Layout::horizontal([
+            Constraint::Length(LABEL_WIDTH + CELL_WIDTH * 8 + 2),
+            Constraint::Min(16),
+        ])
+        .areas(main);
+        let board = Rect {
+            height: board.height.min(11),
+            ..board
+        };
+
+        let block = Block::bordered().title(" オセロ ");
+        self.board_area = block.inner(board);
+        frame.render_widget(Paragraph::new(self.board_lines()).block(block), board);
+
+        let block = Block::bordered().title(" 棋譜 ");
+        let lines = self.move_lines();
+        // 入りきらなければ新しい手を見せる
+        let height = block.inner(moves).height as usize;
+        let skip = lines.len().saturating_sub(height);
+        frame.render_widget(
+            Paragraph::new(lines.into_iter().skip(skip).collect::<Vec<_>>()).block(block),
+            moves,
+        );
+
+        frame.render_widget(
+            Paragraph::new(self.status_lines()).block(Block::bordered()),
+            status,
+        );
+    }
+
+    fn board_lines(&self) -> Vec<Line<'static>> {
+        let osero = self.game.board();
+        let with = self.game.turn();
+        let legal = osero.get_moveable_points(with);
+        let last = self.last_move();
+        let human = self.players[side(with)].is_human() && !self.game.is_finished();
+
+        let mut header = vec![Span::raw(" ".repeat(LABEL_WIDTH as usize))];
+        header.extend(('a'..='h').map(|c| Span::raw(format!(" {} ", c))));
+        let mut lines = vec![Line::from(header)];
+        for y in 0..8 {
+            let mut spans = vec![Span::raw(format!("{} ", y + 1))];
+            for x in 0..8 {
+                let at = (x, y);
+                let (text, fg) = match osero.get(at) {
+                    Some(Pattern::Black) => ("●", Color::Black),
+                    Some(Pattern::White) => ("●", Color::White),
+                    _ if human && legal.contains(&at) => ("·", Color::Yellow),
+                    _ => (" ", Color::Black),
+                };
+                let bg = match &last {
+                    Some((last, _)) if *last == at => Color::LightRed,
+                    Some((_, flipped)) if flipped.contains(&at) => Color::LightGreen,
+                    _ => Color::Green,
+                };
+                let mut style = Style::default().fg(fg).bg(bg);
+                if human && self.cursor == at {
+                    style = style.bg(Color::Blue).add_modifier(Modifier::BOLD);
+                }
+                spans.push(Span::styled(format!(" {} ", text), style));
+            }
+            lines.push(Line::from(spans));
+        }
+        lines
+    }
+
+    // 1行に黒と白の1手ずつ
+    fn move_lines(&self) -> Vec<Line<'static>> {
+        let (_, start) = self.game.start();
+        let mut moves: Vec<String> = self.game.moves().iter().map(Move::to_string).collect();
+        // 白から始めた対局は黒の欄を空ける
+        if start == Pattern::White {
+            moves.insert(0, "--".to_string());
+        }
+        moves
+            .chunks(2)
+            .enumerate()
+            .map(|(i, pair)| Line::from(format!("{:>3}. {}", i + 1, pair.join("  "))))
+            .collect()
+    }
+
+    fn status_lines(&self) -> Vec<Line<'static>> {
+        let osero = self.game.board();
+        let (black, white, empty) = osero.many();
+        let with = self.game.turn();
+        let turn = if self.game.is_finished() {
+            "終局".to_string()
+        } else {
+            let player = match &self.players[side(with)] {
+                Player::Human => "人".to_string(),
+                Player::Ai { name, .. } => format!("AI {}", name),
+            };
+            format!("{}の番（{}）", color_name(with), player)
+        };
+        let opening = self.players.iter().find_map(|player| match player {
+            Player::Ai { ai, .. } => ai.opening_name(osero, with),
+            Player::Human => None,
+        });
+        let mut status = format!(
+            "黒 ●: {}　白 ○: {}　空き: {}　{}",
+            black, white, empty, turn
+        );
+        if let Some(name) = opening {
+            status.push_str(&format!("　定石: {}", name));
+        }
+        if !self.message.is_empty() {
+            status.push_str(&format!("　{}", self.message));
+        }
+        vec![
+            Line::from(status),
+            Line::from(
+                "矢印・hjkl: 移動　Enter・Space・クリック: 置く　u: 待った　r: やり直し　q: 終了",
+            )
+            .style(Style::default().fg(Color::DarkGray)),
+        ]
+    }
+
+    // 画面の位置にあるマス
+    fn cell_at(&self, column: u16, row: u16) -> Option<(usize, usize)> {
+        let area = self.board_area;
+        let x = column.checked_sub(area.x + LABEL_WIDTH)? / CELL_WIDTH;
+        let y = row.checked_sub(area.y + 1)?;
+        (x < 8 && y < 8).then_some((x as usize, y as usize))
+    }
+
+    fn put(&mut self, at: (usize, usize)) {
+        let with = self.game.turn();
+        if !self.players[side(with)].is_human() || self.game.is_finished() {
+            return;
+        }
+        if self.game.play(Move::from(at)) {
+            self.message.clear();
+        } else {
+            self.message = format!("{} には置けません", Move::from(at));
+        }
+    }
+
+    // 人が打てる番まで戻す
+    fn undo(&mut self) {
+        if !self.players.iter().any(Player::is_human) {
+            return;
+        }
+        let mut undone = 0;
+        while self.game.undo().is_some() {
+            undone += 1;
+            if human_can_move(&self.game, &self.players) {
+                break;
+            }
+        }
+        self.message = match undone {
+            0 => "戻せる手がありません".to_string(),
+            n => format!("{} 手戻しました", n),
+        };
+    }
+
+    fn redo(&mut self) {
+        let mut redone = 0;
+        while self.game.redo().is_some() {
+            redone += 1;
+            if human_can_move(&self.game, &self.players) {
+                break;
+            }
+        }
+        self.message = match redone {
+            0 => "やり直せる手がありません".to_string(),
+            n => format!("{} 手やり直しました", n),
+        };
+    }
+
+    // 終局していれば結果を、そうでなければパスや AI の手を進める。人の入力を待つなら false
+    fn advance(&mut self, terminal: &mut DefaultTerminal) -> io::Result<bool> {
+        if self.game.is_finished() {
+            self.message = match self.game.board().which_win() {
+                Some(winner) => format!("{}の勝ちです", color_name(winner)),
+                None => "引き分けです".to_string(),
+            };
+            return Ok(false);
+        }
+        let with = self.game.turn();
+        if self.game.pass_if_forced() {
+            self.message = format!("{}は置けないのでパスしました", color_name(with));
+            return Ok(true);
+        }
+        if self.players[side(with)].is_human() {
+            return Ok(false);
+        }
+        self.message = "AI が考え中…".to_string();
+        terminal.draw(|frame| self.draw(frame))?;
+        let Player::Ai { ai, level, .. } = &self.players[side(with)] else {
+            return Ok(false);
+        };
+        let osero = *self.game.board();
+        let best = ai.think(&osero, with, *level, self.move_time);
+        let mv = Move::from_at(best);
+        self.game.play(mv);
+        self.message = format!("AI（{}）は {} に置きました", color_name(with), mv);
+        Ok(true)
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            // AI が打ったあとも入力があれば読む。AI どうしでも q で止められる
+            let waiting = !self.advance(terminal)?;
+            if !waiting && !event::poll(Duration::ZERO)? {
+                continue;
+            }
+            if waiting {
+                terminal.draw(|frame| self.draw(frame))?;
+            }
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => {
+                    let (x, y) = self.cursor;
+                    match key.code {
+                        KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                        KeyCode::Left | KeyCode::Char('h') => self.cursor = ((x + 7) % 8, y),
+                        KeyCode::Right | KeyCode::Char('l') => self.cursor = ((x + 1) % 8, y),
+                        KeyCode::Up | KeyCode::Char('k') => self.cursor = (x, (y + 7) % 8),
+                        KeyCode::Down | KeyCode::Char('j') => self.cursor = (x, (y + 1) % 8),
+                        KeyCode::Enter | KeyCode::Char(' ') => self.put(self.cursor),
+                        KeyCode::Char('u') => self.undo(),
+                        KeyCode::Char('r') => self.redo(),
+                        _ => {}
+                    }
+                }
+                Event::Mouse(mouse) => {
+                    if let Some(at) = self.cell_at(mouse.column, mouse.row) {
+                        self.cursor = at;
+                        if mouse.kind == MouseEventKind::Down(MouseButton::Left) {
+                            self.put(at);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+pub fn run(args: Vec<String>) -> Result<(), String> {
+    let options = play::parse_options(args)?;
+    if options.clock.is_some() {
+        return Err("TUI では --clock は使えません".to_string());
+    }
+    let mode = options.mode.unwrap_or(play::Mode::HumanAi);
+    let level = options.level.unwrap_or(DEFAULT_LEVEL);
+    let players = play::players(&options, mode.humans(options.color), level)?;
+    let game = match options.position {
+        Some((osero, turn)) => Game::from_position(osero, turn),
+        None => Game::new(),
+    };
+    // 最初の手番で置けるマスにカーソルを置く
+    let cursor = game
+        .board()
+        .get_moveable_points(game.turn())
+        .first()
+        .copied()
+        .unwrap_or((3, 3));
+    let mut app = App {
+        game,
+        players,
+        move_time: options.move_time,
+        cursor,
+        message: String::new(),
+        board_area: Rect::default(),
+    };
+
+    let mut terminal = ratatui::init();
+    let result =
+        crossterm::execute!(io::stdout(), EnableMouseCapture).and_then(|()| app.run(&mut terminal));
+    crossterm::execute!(io::stdout(), DisableMouseCapture).ok();
+    ratatui::restore();
+    result.map_err(|e| e.to_string())
+}
@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use osero::{
+    GgfGame, Move, OpeningBook, Sample, WthorDatabase, format_moves, read_wthor_names,
+    samples_from_game, write_samples,
+};
+
+use super::value;
+
+const USAGE: &str = "使い方: osero wthor [オプション] ファイル.wtb...
+  WTHOR の対局を読み、すべての手が合法かを確かめて集計する
+  --players ファイル.jou    対局者の名前を読む
+  --tournaments ファイル    大会の名前（.trn）を読み、GGF の PC（場所）に書く
+  --openings N              最初の N 手でまとめた多い順の手順を表示する（既定 6）
+  --book ファイル           最初の --depth 手を定石として覚えて書き出す
+  --depth N                 定石に覚える手数（既定 12）
+  --samples ファイル        評価関数の学習用の局面を書き出す（osero train --samples で使う）
+  --ggf ファイル            読んだ対局を GGF で書き出す";
+
+pub fn run(args: Vec<String>) -> Result<(), String> {
+    let mut paths = vec![];
+    let mut players_path: Option<String> = None;
+    let mut tournaments_path: Option<String> = None;
+    let mut openings = 6;
+    let mut book_path: Option<String> = None;
+    let mut depth = 12;
+    let mut samples_path: Option<String> = None;
+    let mut ggf_path: Option<String> = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--players" => players_path = Some(value(&mut args, "--players")?),
+            "--tournaments" => tournaments_path = Some(value(&mut args, "--tournaments")?),
+            "--openings" => openings = value(&mut args, "--openings")?,
+            "--book" => book_path = Some(value(&mut args, "--book")?),
+            "--depth" => depth = value(&mut args, "--depth")?,
+            "--samples" => samples_path = Some(value(&mut args, "--samples")?),
+            "--ggf" => ggf_path = Some(value(&mut args, "--ggf")?),
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            _ if arg.starts_with("--") => {
+                return Err(format!("{} は知らないオプションです\n{}", arg, USAGE));
+            }
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    let players = match players_path {
+        Some(path) => read_wthor_names(&path)
+            .map_err(|e| format!("対局者のファイル {} を読めません: {}", path, e))?,
+        None => vec![],
+    };
+    let tournaments = match tournaments_path {
+        Some(path) => read_wthor_names(&path)
+            .map_err(|e| format!("大会のファイル {} を読めません: {}", path, e))?,
+        None => vec![],
+    };
+
+    let mut databases = vec![];
+    for path in paths.iter() {
+        let database = WthorDatabase::load(path)
+            .map_err(|e| format!("WTHOR のファイル {} を読めません: {}", path, e))?;
+        for (i, error) in database.invalid.iter() {
+            println!("{} の {}局目: {}", path, i + 1, error);
+        }
+        databases.push(database);
+    }
+    let games: Vec<_> = databases.iter().flat_map(|d| d.games.iter()).collect();
+    let invalid: usize = databases.iter().map(|d| d.invalid.len()).sum();
+
+    // 黒の [勝ち, 負け, 引き分け]
+    let mut results = [0; 3];
+    let mut discs = 0;
+    let mut lines: HashMap<String, usize> = HashMap::new();
+    for game in games.iter() {
+        let black = game.black_discs as i32;
+        discs += black;
+        results[match black.cmp(&32) {
+            std::cmp::Ordering::Greater => 0,
+            std::cmp::Ordering::Less => 1,
+            std::cmp::Ordering::Equal => 2,
+        }] += 1;
+        let moves = game.game.moves();
+        if moves.len() >= openings {
+            *lines.entry(format_moves(&moves[..openings])).or_default() += 1;
+        }
+    }
+    println!(
+        "{} 局（読めなかった対局 {} 局）　黒の {}勝 {}敗 {}分　黒の石の平均 {:.1}",
+        games.len(),
+        invalid,
+        results[0],
+        results[1],
+        results[2],
+        discs as f64 / games.len().max(1) as f64
+    );
+    let mut lines: Vec<(String, usize)> = lines.into_iter().collect();
+    lines.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    for (line, count) in lines.iter().take(10) {
+        println!("{:>7} 局  {}", count, line);
+    }
+
+    if let Some(path) = book_path {
+        let mut book = OpeningBook::new();
+        for game in games.iter() {
+            let moves: Vec<(usize, usize)> =
+                game.game.moves().iter().filter_map(Move::at).collect();
+            book.learn(&moves, depth);
+        }
+        book.save(&path)
+            .map_err(|e| format!("定石を {} に保存できません: {}", path, e))?;
+        println!("{} 局面の定石を {} に書き出しました", book.len(), path);
+    }
+    if let Some(path) = samples_path {
+        let samples: Vec<Sample> = games
+            .iter()
+            .flat_map(|game| samples_from_game(&game.game))
+            .collect();
+        write_samples(&path, &samples)
+            .map_err(|e| format!("局面を {} に保存できません: {}", path, e))?;
+        println!("{} 局面を {} に書き出しました", samples.len(), path);
+    }
+    if let Some(path) = ggf_path {
+        let write = || -> std::io::Result<()> {
+            let mut writer = BufWriter::new(File::create(&path)?);
+            for database in databases.iter() {
+                for game in database.games.iter() {
+                    let record = game.to_record(database.header.year, &players);
+                    let mut ggf = GgfGame::from(record);
+                    if let Some(name) = tournaments.get(game.tournament as usize) {
+                        ggf.tags.push(("PC".to_string(), name.clone()));
+                    }
+                    writeln!(writer, "{}", ggf)?;
+                }
+            }
+            writer.flush()
+        };
+        write().map_err(|e| format!("GGF を {} に保存できません: {}", path, e))?;
+        println!("{} 局を GGF で {} に書き出しました", games.len(), path);
+    }
+    Ok(())
+}
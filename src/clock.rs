@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+/// 対局時計。持ち時間を使い切ったら時間切れ。`increment` が 0 ならサドンデス、
+/// そうでなければ1手指すごとに加算するフィッシャールール
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Clock {
+    remaining: Duration,
+    increment: Duration,
+}
+
+impl Clock {
+    pub fn sudden_death(total: Duration) -> Self {
+        Clock::fischer(total, Duration::ZERO)
+    }
+
+    pub fn fischer(total: Duration, increment: Duration) -> Self {
+        Clock {
+            remaining: total,
+            increment,
+        }
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.remaining
+    }
+
+    pub fn increment(&self) -> Duration {
+        self.increment
+    }
+
+    pub fn is_flagged(&self) -> bool {
+        self.remaining.is_zero()
+    }
+
+    /// 1手に `elapsed` かかったとして時計を進める。時間切れなら false
+    pub fn spend(&mut self, elapsed: Duration) -> bool {
+        if elapsed >= self.remaining {
+            self.remaining = Duration::ZERO;
+            return false;
+        }
+        self.remaining = self.remaining - elapsed + self.increment;
+        true
+    }
+
+    /// 空きマスが `empties` のときに1手に使ってよい時間
+    pub fn budget(&self, empties: usize) -> Duration {
+        // 自分の残りの手数は空きマスの半分くらい。読み切りに入る終盤の分を少し残しておく
+        let moves_left = (empties as u32 / 2).max(1) + 2;
+        let budget = self.remaining / moves_left + self.increment * 3 / 4;
+        // 時計が切れないように残り時間の半分は超えない
+        budget.min(self.remaining / 2)
+    }
+}
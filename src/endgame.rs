@@ -0,0 +1,279 @@
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+use crate::bitboard::{self, bit};
+use crate::search::Limit;
+use crate::{Osero, Pattern};
+
+// 4つの象限。偶数理論（パリティ）で空きマスの数を数える単位
+const QUADRANTS: [u64; 4] = [
+    0x0000_0000_0f0f_0f0f,
+    0x0000_0000_f0f0_f0f0,
+    0x0f0f_0f0f_0000_0000,
+    0xf0f0_f0f0_0000_0000,
+];
+
+// 空きマスがこれより多い間は、相手の着手可能数が少ない手から読む
+const FASTEST_FIRST_EMPTIES: u32 = 6;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EndgameResult {
+    pub best_move: Option<(usize, usize)>,
+    /// 最後まで打ったときの `with` から見た石差。空きマスは勝った側に数える
+    pub score: i32,
+    pub nodes: u64,
+}
+
+impl EndgameResult {
+    pub fn outcome(&self) -> Outcome {
+        match self.score {
+            s if s > 0 => Outcome::Win,
+            s if s < 0 => Outcome::Loss,
+            _ => Outcome::Draw,
+        }
+    }
+}
+
+impl Osero {
+    /// 終局まで読み切って最善手と最終的な石差を求める
+    pub fn solve_endgame(&self, with: Pattern) -> EndgameResult {
+        self.solve_endgame_within(with, &Limit::new(None))
+            .expect("時間の制限がなければ必ず読み切る")
+    }
+
+    /// `limit` の時間内に読み切れなければ None を返す
+    pub(crate) fn solve_endgame_within(
+        &self,
+        with: Pattern,
+        limit: &Limit,
+    ) -> Option<EndgameResult> {
+        let (player, opponent) = self.sides(with);
+        let moves = self.get_moveable_points(with);
+        let solver = || Solver { limit, nodes: 0 };
+        if moves.is_empty() {
+            let mut solver = solver();
+            let score = solver.solve(player, opponent, -65, 65, false);
+            return (!limit.is_stopped()).then_some(EndgameResult {
+                best_move: None,
+                score,
+                nodes: solver.nodes,
+            });
+        }
+
+        let child = |at: (usize, usize)| {
+            let put = bit(at);
+            let flips = bitboard::flips(player, opponent, put);
+            (opponent & !flips, player | put | flips)
+        };
+
+        // 中盤の探索と同じく、最初の手を全幅で読んで残りを並列に null window で読む
+        let mut first = solver();
+        let (p, o) = child(moves[0]);
+        let alpha = -first.solve(p, o, -65, 65, false);
+
+        let rest = moves[1..]
+            .par_iter()
+            .map(|at| {
+                let mut solver = solver();
+                let (p, o) = child(*at);
+                let mut score = -solver.solve(p, o, -alpha - 1, -alpha, false);
+                if score > alpha {
+                    score = -solver.solve(p, o, -65, -alpha, false);
+                }
+                (score, solver.nodes)
+            })
+            .collect::<Vec<(i32, u64)>>();
+
+        if limit.is_stopped() {
+            return None;
+        }
+        let mut best = (moves[0], alpha);
+        let mut nodes = first.nodes;
+        for (i, (score, n)) in rest.into_iter().enumerate() {
+            nodes += n;
+            if score > best.1 {
+                best = (moves[i + 1], score);
+            }
+        }
+        Some(EndgameResult {
+            best_move: Some(best.0),
+            score: best.1,
+            nodes,
+        })
+    }
+}
+
+struct Solver<'a> {
+    limit: &'a Limit,
+    nodes: u64,
+}
+
+impl Solver<'_> {
+    fn solve(
+        &mut self,
+        player: u64,
+        opponent: u64,
+        mut alpha: i32,
+        beta: i32,
+        passed: bool,
+    ) -> i32 {
+        self.nodes += 1;
+        // 時間切れなら値は捨てられるので、すぐに戻る
+        if self.nodes & 1023 == 0 && self.limit.check() {
+            return 0;
+        }
+        let empty = !(player | opponent);
+        if empty == 0 {
+            return final_difference(player, opponent);
+        }
+        let moves = bitboard::legal_moves(player, opponent);
+        if moves == 0 {
+            if passed {
+                return final_difference(player, opponent);
+            }
+            return -self.solve(opponent, player, -beta, -alpha, true);
+        }
+
+        // (並べ替えのキー, 置く位置, 返る石)
+        let mut list = [(0u32, 0u64, 0u64); 64];
+        let mut len = 0;
+        let parity = QUADRANTS
+            .iter()
+            .filter(|q| (*q & empty).count_ones() % 2 == 1)
+            .fold(0, |acc, q| acc | q);
+        let fastest_first = empty.count_ones() > FASTEST_FIRST_EMPTIES;
+        let mut bits = moves;
+        while bits != 0 {
+            let put = bits & bits.wrapping_neg();
+            bits &= bits - 1;
+            let flips = bitboard::flips(player, opponent, put);
+            // 空きマスが奇数の象限に打つ手を先に読む
+            let mut key = u32::from(put & parity == 0);
+            if fastest_first {
+                let mobility =
+                    bitboard::legal_moves(opponent & !flips, player | put | flips).count_ones();
+                key |= mobility << 1;
+            }
+            list[len] = (key, put, flips);
+            len += 1;
+        }
+        list[..len].sort_unstable_by_key(|m| m.0);
+
+        let mut best = -65;
+        for (i, &(_, put, flips)) in list[..len].iter().enumerate() {
+            let (p, o) = (opponent & !flips, player | put | flips);
+            let score = if i == 0 {
+                -self.solve(p, o, -beta, -alpha, false)
+            } else {
+                let score = -self.solve(p, o, -alpha - 1, -alpha, false);
+                if score > alpha && score < beta {
+                    -self.solve(p, o, -beta, -score, false)
+                } else {
+                    score
+                }
+            };
+            if self.limit.is_stopped() {
+                return 0;
+            }
+            if score > best {
+                best = score;
+                if score > alpha {
+                    alpha = score;
+                    if alpha >= beta {
+                        break;
+                    }
+                }
+            }
+        }
+        best
+    }
+}
+
+pub(crate) fn final_difference(player: u64, opponent: u64) -> i32 {
+    let player = player.count_ones() as i32;
+    let opponent = opponent.count_ones() as i32;
+    let empty = 64 - player - opponent;
+    match player.cmp(&opponent) {
+        std::cmp::Ordering::Greater => player - opponent + empty,
+        std::cmp::Ordering::Less => player - opponent - empty,
+        std::cmp::Ordering::Equal => 0,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+
+    /// 枝刈りをしない素朴な negamax で、`player` から見た最終的な石差を求める
+    pub(crate) fn negamax(player: u64, opponent: u64, passed: bool) -> i32 {
+        let moves = bitboard::legal_moves(player, opponent);
+        if moves == 0 {
+            if passed {
+                return final_difference(player, opponent);
+            }
+            return -negamax(opponent, player, true);
+        }
+        bitboard::squares(moves)
+            .map(|at| {
+                let put = bit(at);
+                let flips = bitboard::flips(player, opponent, put);
+                -negamax(opponent & !flips, player | put | flips, false)
+            })
+            .max()
+            .unwrap()
+    }
+
+    /// 初期局面から乱数で打ち進め、空きマスが `empties` 個の終わっていない局面を作る
+    pub(crate) fn random_endgame(seed: u64, empties: usize) -> (Osero, Pattern) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        loop {
+            let mut osero = Osero::default();
+            let mut with = Pattern::Black;
+            while !osero.is_finished() && osero.many().2 > empties {
+                let moves = osero.get_moveable_points(with);
+                if !moves.is_empty() {
+                    osero.put(with, moves[rng.random_range(0..moves.len())]);
+                }
+                with = with.fliped();
+            }
+            if !osero.is_finished() {
+                return (osero, with);
+            }
+        }
+    }
+
+    #[test]
+    fn solve_matches_negamax() {
+        for seed in 0..8 {
+            let (osero, with) = random_endgame(seed, 9);
+            let (player, opponent) = osero.sides(with);
+            let result = osero.solve_endgame(with);
+            assert_eq!(
+                result.score,
+                negamax(player, opponent, false),
+                "seed {}",
+                seed
+            );
+
+            // 最善手を打った先も同じ石差になる
+            match result.best_move {
+                Some(at) => {
+                    let put = bit(at);
+                    let flips = bitboard::flips(player, opponent, put);
+                    let after = -negamax(opponent & !flips, player | put | flips, false);
+                    assert_eq!(after, result.score, "seed {}", seed);
+                }
+                None => assert_eq!(bitboard::legal_moves(player, opponent), 0),
+            }
+        }
+    }
+}
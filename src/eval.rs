@@ -0,0 +1,164 @@
+use std::sync::LazyLock;
+
+use crate::bitboard::{self, DIRECTIONS};
+use crate::search::FINAL_SCORE_UNIT;
+use crate::{Osero, POSITION_SCORE, Pattern};
+
+const CORNERS: u64 = 0x8100_0000_0000_0081;
+
+/// 局面の評価関数。探索の末端で使う
+pub trait Evaluator: Send + Sync {
+    /// `with` の手番の局面を `with` から見て評価する。大きいほど `with` が有利
+    fn evaluate(&self, osero: &Osero, with: Pattern) -> i32;
+
+    /// 評価値のうち石1つの差にあたる大きさ。評価値を石差の見込みに直すのに使う
+    fn disc_scale(&self) -> i32 {
+        FINAL_SCORE_UNIT
+    }
+}
+
+/// 置いた石の位置の点数と石数の差だけを見る、もともとの評価
+#[derive(Clone, Copy, Default, Debug)]
+pub struct PositionalEvaluator;
+
+impl Evaluator for PositionalEvaluator {
+    fn evaluate(&self, osero: &Osero, with: Pattern) -> i32 {
+        let (player, opponent) = osero.sides(with);
+        let positional = |bits: u64| {
+            bitboard::squares(bits)
+                .map(|at| POSITION_SCORE[at.1 * 8 + at.0])
+                .sum::<i32>()
+        };
+        let discs = player.count_ones() as i32 - opponent.count_ones() as i32;
+        positional(player) - positional(opponent) + discs * 10
+    }
+
+    // 自己対局の途中の評価値と終局の石差を比べて決めた
+    fn disc_scale(&self) -> i32 {
+        14
+    }
+}
+
+/// 序盤（空き60）と終盤（空き0）の重み。間の局面は空きマスの数で線形に混ぜる
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Weight {
+    pub opening: i32,
+    pub endgame: i32,
+}
+
+impl Weight {
+    pub const fn new(opening: i32, endgame: i32) -> Self {
+        Weight { opening, endgame }
+    }
+
+    fn at(&self, empties: i32) -> i32 {
+        let empties = empties.min(60);
+        (self.opening * empties + self.endgame * (60 - empties)) / 60
+    }
+}
+
+/// 着手可能数・潜在的着手可能数・開放度・確定石・隅・偶数理論を組み合わせた評価
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct StandardEvaluator {
+    /// 着手可能数の差
+    pub mobility: Weight,
+    /// 相手の石に接する空きマスの数の差
+    pub potential_mobility: Weight,
+    /// 空きマスに接する石（開放度）の数の差。少ないほうがよい
+    pub frontier: Weight,
+    /// 確定石の数の差
+    pub stable: Weight,
+    /// 隅の石の数の差
+    pub corner: Weight,
+    /// 空きマスが奇数で最後の1手を打てる見込みがあるか
+    pub parity: Weight,
+}
+
+impl Default for StandardEvaluator {
+    fn default() -> Self {
+        StandardEvaluator {
+            mobility: Weight::new(30, 10),
+            potential_mobility: Weight::new(10, 2),
+            frontier: Weight::new(12, 4),
+            stable: Weight::new(40, 30),
+            corner: Weight::new(80, 20),
+            parity: Weight::new(0, 30),
+        }
+    }
+}
+
+impl Evaluator for StandardEvaluator {
+    fn evaluate(&self, osero: &Osero, with: Pattern) -> i32 {
+        let (player, opponent) = osero.sides(with);
+        let empty = !(player | opponent);
+        let empties = empty.count_ones() as i32;
+        let count = |bits: u64| bits.count_ones() as i32;
+
+        let mobility = count(bitboard::legal_moves(player, opponent))
+            - count(bitboard::legal_moves(opponent, player));
+        let potential_mobility = count(bitboard::neighbours(opponent) & empty)
+            - count(bitboard::neighbours(player) & empty);
+        let frontier = count(bitboard::neighbours(empty) & opponent)
+            - count(bitboard::neighbours(empty) & player);
+        let stable = count(stable_discs(player, opponent)) - count(stable_discs(opponent, player));
+        let corner = count(player & CORNERS) - count(opponent & CORNERS);
+        let parity = if empties % 2 == 1 { 1 } else { -1 };
+
+        self.mobility.at(empties) * mobility
+            + self.potential_mobility.at(empties) * potential_mobility
+            + self.frontier.at(empties) * frontier
+            + self.stable.at(empties) * stable
+            + self.corner.at(empties) * corner
+            + self.parity.at(empties) * parity
+    }
+
+    // 既定の重みで、自己対局の途中の評価値と終局の石差を比べて決めた
+    fn disc_scale(&self) -> i32 {
+        20
+    }
+}
+
+// 縦・横・2つの斜めの4本の線について、盤上にあるすべての線
+static LINES: LazyLock<[Vec<u64>; 4]> = LazyLock::new(|| {
+    std::array::from_fn(|axis| {
+        let (forward, backward) = (DIRECTIONS[axis * 2], DIRECTIONS[axis * 2 + 1]);
+        let mut lines: Vec<u64> = vec![];
+        for i in 0..64 {
+            let mut line = 1u64 << i;
+            for _ in 0..7 {
+                line |= forward(line) | backward(line);
+            }
+            if !lines.contains(&line) {
+                lines.push(line);
+            }
+        }
+        lines
+    })
+});
+
+/// 確定石を数える。4本の線のどれについても、線が埋まっているか、
+/// 盤の端か確定石に接していれば返されることはない
+pub(crate) fn stable_discs(own: u64, other: u64) -> u64 {
+    let filled = own | other;
+    let full: [u64; 4] = std::array::from_fn(|axis| {
+        LINES[axis]
+            .iter()
+            .filter(|line| filled & **line == **line)
+            .fold(0, |acc, line| acc | line)
+    });
+
+    let mut stable = own & CORNERS;
+    loop {
+        let mut next = own;
+        for (axis, full) in full.iter().enumerate() {
+            let (forward, backward) = (DIRECTIONS[axis * 2], DIRECTIONS[axis * 2 + 1]);
+            // 線の向きに隣がなければ端にいる
+            let edge = !(forward(u64::MAX) & backward(u64::MAX));
+            next &= full | edge | forward(stable) | backward(stable);
+        }
+        if next == stable {
+            return stable;
+        }
+        stable = next;
+    }
+}
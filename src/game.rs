@@ -0,0 +1,205 @@
+use crate::{Move, Osero, Pattern};
+
+/// 1局の記録。開始局面から打った手（パスも含む）をすべて持ち、待ったとやり直しができる
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Game {
+    start: Osero,
+    start_turn: Pattern,
+    // 打った手と、その手を打つ前の局面と手番
+    history: Vec<(Move, Osero, Pattern)>,
+    // 待ったで戻した手。新しい手を打つと消える
+    undone: Vec<Move>,
+    osero: Osero,
+    turn: Pattern,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Game::from_position(Osero::default(), Pattern::Black)
+    }
+}
+
+impl Game {
+    pub fn new() -> Self {
+        Game::default()
+    }
+
+    /// 途中の局面から始める
+    pub fn from_position(osero: Osero, turn: Pattern) -> Self {
+        Game {
+            start: osero,
+            start_turn: turn,
+            history: vec![],
+            undone: vec![],
+            osero,
+            turn,
+        }
+    }
+
+    pub fn board(&self) -> &Osero {
+        &self.osero
+    }
+
+    /// 次に打つ側
+    pub fn turn(&self) -> Pattern {
+        self.turn
+    }
+
+    pub fn start(&self) -> (&Osero, Pattern) {
+        (&self.start, self.start_turn)
+    }
+
+    /// これまでに打った手。パスも含む
+    pub fn moves(&self) -> Vec<Move> {
+        self.history.iter().map(|(mv, _, _)| *mv).collect()
+    }
+
+    /// 手を打つ前の局面と手番を順に返す。パスした局面は除く
+    pub fn positions(&self) -> Vec<(Osero, Pattern)> {
+        let (start, turn) = self.start();
+        let mut replay = Game::from_position(*start, turn);
+        let mut positions = vec![];
+        for mv in self.moves() {
+            if mv != Move::Pass {
+                positions.push((*replay.board(), replay.turn()));
+            }
+            replay.play(mv);
+        }
+        positions
+    }
+
+    pub fn legal_moves(&self) -> Vec<Move> {
+        self.osero.legal_moves(self.turn)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.osero.is_finished()
+    }
+
+    /// 手番側が `mv` を打つ。打てない手なら何もせずに false を返す
+    pub fn play(&mut self, mv: Move) -> bool {
+        if !self.push(mv) {
+            return false;
+        }
+        self.undone.clear();
+        true
+    }
+
+    /// 手番の側がパスするしかなければパスして true を返す
+    pub fn pass_if_forced(&mut self) -> bool {
+        self.legal_moves() == [Move::Pass] && self.play(Move::Pass)
+    }
+
+    fn push(&mut self, mv: Move) -> bool {
+        let before = self.osero;
+        if !self.osero.play(self.turn, mv) {
+            return false;
+        }
+        self.history.push((mv, before, self.turn));
+        self.turn = self.turn.fliped();
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.history.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// 最後の手を取り消して、その手を打つ前の局面に戻す
+    pub fn undo(&mut self) -> Option<Move> {
+        let (mv, osero, turn) = self.history.pop()?;
+        self.osero = osero;
+        self.turn = turn;
+        self.undone.push(mv);
+        Some(mv)
+    }
+
+    /// 最後に取り消した手を打ち直す
+    pub fn redo(&mut self) -> Option<Move> {
+        let mv = self.undone.pop()?;
+        self.push(mv);
+        Some(mv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_moves;
+
+    // 白が2回パスする対局
+    const MOVES: &str = "c4c5e6e3c6d6f2f5d7e2g4g2g1e7e8pah2pad2";
+
+    #[test]
+    fn undo_and_redo_restore_positions() {
+        let mut game = Game::new();
+        let mut states = vec![(*game.board(), game.turn())];
+        for mv in parse_moves(MOVES).unwrap() {
+            assert!(game.play(mv));
+            states.push((*game.board(), game.turn()));
+        }
+        assert!(game.is_finished());
+        let moves = game.moves();
+
+        // パスも1手として戻す
+        for (i, state) in states.iter().enumerate().rev().skip(1) {
+            assert_eq!(game.undo(), Some(moves[i]));
+            assert_eq!((*game.board(), game.turn()), *state);
+        }
+        assert!(!game.can_undo());
+        assert_eq!(game.undo(), None);
+
+        for (i, state) in states.iter().enumerate().skip(1) {
+            assert_eq!(game.redo(), Some(moves[i - 1]));
+            assert_eq!((*game.board(), game.turn()), *state);
+        }
+        assert!(!game.can_redo());
+        assert_eq!(game.moves(), moves);
+    }
+
+    #[test]
+    fn new_move_clears_redo() {
+        let mut game = Game::new();
+        for mv in parse_moves("f5d6c3").unwrap() {
+            game.play(mv);
+        }
+        game.undo();
+        game.undo();
+        assert!(game.can_redo());
+        // d6 のかわりに f4 を打つと、取り消した d6 c3 は打ち直せない
+        assert!(game.play(Move::Put(5, 3)));
+        assert!(!game.can_redo());
+        assert_eq!(game.redo(), None);
+        assert_eq!(game.moves(), parse_moves("f5f4").unwrap());
+    }
+
+    #[test]
+    fn illegal_moves_change_nothing() {
+        let mut game = Game::new();
+        game.play(Move::Put(5, 4));
+        game.undo();
+        let before = game.clone();
+        assert!(!game.play(Move::Put(0, 0)));
+        assert!(!game.play(Move::Pass));
+        assert_eq!(game, before);
+        assert!(game.can_redo());
+    }
+
+    #[test]
+    fn passes_only_when_forced() {
+        let mut game = Game::new();
+        assert!(!game.pass_if_forced());
+        for mv in parse_moves("c4c5e6e3c6d6f2f5d7e2g4g2g1e7e8").unwrap() {
+            game.play(mv);
+        }
+        assert_eq!(game.turn(), Pattern::White);
+        assert!(game.pass_if_forced());
+        assert_eq!(game.turn(), Pattern::Black);
+        assert_eq!(game.moves().last(), Some(&Move::Pass));
+        // 打った局面だけを返し、パスした局面は含めない
+        assert_eq!(game.positions().len(), 15);
+    }
+}
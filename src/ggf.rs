@@ -0,0 +1,369 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Game, Move, Osero, ParseGameError, Pattern, Record};
+
+/// 1手に添えられた評価値と消費時間（秒）
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct MoveNote {
+    pub eval: Option<f64>,
+    pub time: Option<f64>,
+}
+
+/// GGF（Generic Game Format）の1局。`(;GM[Othello]PB[..]PW[..]BO[..]B[f5]W[d6//1.5];)` の形
+#[derive(Clone, PartialEq, Debug)]
+pub struct GgfGame {
+    pub record: Record,
+    /// 打った手ごとの評価値と時間。`record.game.moves()` と同じ長さ
+    pub notes: Vec<MoveNote>,
+    /// RE の値。黒から見た石差で、`:r`（投了）などが続くこともある。
+    /// 終局していれば書き出すときは手順から求め直す
+    pub result: Option<String>,
+    /// PC（場所）や TI（持ち時間）など、ほかの項目。書き出すときもそのまま書く
+    pub tags: Vec<(String, String)>,
+}
+
+impl GgfGame {
+    pub fn new(record: Record) -> Self {
+        let notes = vec![MoveNote::default(); record.game.moves().len()];
+        GgfGame {
+            record,
+            notes,
+            result: None,
+            tags: vec![],
+        }
+    }
+
+    /// 続けて書かれた複数の対局を読む。オセロ以外の対局や 8x8 以外の盤はエラー
+    pub fn parse_all(text: &str) -> Result<Vec<GgfGame>, ParseGameError> {
+        let mut games = vec![];
+        let mut rest = text;
+        while let Some(start) = rest.find("(;") {
+            let end = rest[start..]
+                .find(";)")
+                .ok_or_else(|| format_error("対局の終わりの ;) がありません"))?;
+            games.push(rest[start..start + end + 2].parse()?);
+            rest = &rest[start + end + 2..];
+        }
+        Ok(games)
+    }
+}
+
+impl From<Record> for GgfGame {
+    fn from(record: Record) -> Self {
+        GgfGame::new(record)
+    }
+}
+
+fn format_error(message: &str) -> ParseGameError {
+    ParseGameError::Format(message.to_string())
+}
+
+// "KEY[値]" の組を順に取り出す
+fn properties(body: &str) -> Result<Vec<(String, String)>, ParseGameError> {
+    let mut properties = vec![];
+    let mut rest = body.trim();
+    while !rest.is_empty() {
+        let open = rest
+            .find('[')
+            .ok_or_else(|| format_error("[ がありません"))?;
+        let close = rest[open..]
+            .find(']')
+            .ok_or_else(|| format_error("] がありません"))?;
+        let key = rest[..open].trim().to_string();
+        properties.push((key, rest[open + 1..open + close].to_string()));
+        rest = rest[open + close + 1..].trim_start();
+    }
+    Ok(properties)
+}
+
+// "8 -------- ... ---O*--- ... *" の盤面と手番
+fn parse_board(value: &str) -> Result<(Osero, Pattern), ParseGameError> {
+    let mut words = value.split_whitespace();
+    if words.next() != Some("8") {
+        return Err(format_error("8x8 以外の盤には対応していません"));
+    }
+    let mut cells: String = words.collect();
+    let turn = match cells.pop() {
+        Some('*') => Pattern::Black,
+        Some('O' | 'o') => Pattern::White,
+        _ => return Err(format_error("BO の手番が読めません")),
+    };
+    if cells.chars().count() != 64 {
+        return Err(format_error("BO のマスの数が 64 ではありません"));
+    }
+    let mut board = [Pattern::None; 64];
+    for (cell, c) in board.iter_mut().zip(cells.chars()) {
+        *cell = match c {
+            '*' | 'x' | 'X' => Pattern::Black,
+            'O' | 'o' => Pattern::White,
+            '-' | '.' => Pattern::None,
+            _ => return Err(format_error("BO のマスが読めません")),
+        };
+    }
+    Ok((Osero::from(board), turn))
+}
+
+fn format_board(osero: &Osero, turn: Pattern) -> String {
+    let mut board = "8".to_string();
+    for (i, cell) in osero.cells().iter().enumerate() {
+        if i % 8 == 0 {
+            board.push(' ');
+        }
+        board.push(match cell {
+            Pattern::Black => '*',
+            Pattern::White => 'O',
+            Pattern::None => '-',
+        });
+    }
+    board.push_str(if turn == Pattern::White { " O" } else { " *" });
+    board
+}
+
+// "f5/1.23/4.5" の手と評価値と時間
+fn parse_move(value: &str) -> Result<(Move, MoveNote), ParseGameError> {
+    let mut parts = value.split('/');
+    let mv = parts.next().unwrap_or_default().parse()?;
+    let mut number = || parts.next().and_then(|s| s.trim().parse().ok());
+    let eval = number();
+    let time = number();
+    Ok((mv, MoveNote { eval, time }))
+}
+
+fn format_move(mv: Move, note: MoveNote) -> String {
+    let mv = match mv {
+        Move::Pass => "pa".to_string(),
+        mv => mv.to_string(),
+    };
+    match note {
+        MoveNote {
+            eval: None,
+            time: None,
+        } => mv,
+        MoveNote { eval, time } => format!(
+            "{}/{}/{}",
+            mv,
+            eval.map_or(String::new(), |e| format!("{:.2}", e)),
+            time.map_or(String::new(), |t| format!("{:.2}", t))
+        ),
+    }
+}
+
+// 棋譜の日時 "2024-01-02 03:04:05 UTC" を GGF の "2024.01.02_03:04:05.UTC" にする
+fn ggf_date(date: &str) -> String {
+    match date.split_once(' ') {
+        Some((day, time)) => format!("{}_{}", day.replace('-', "."), time.replacen(' ', ".", 1)),
+        None => date.to_string(),
+    }
+}
+
+impl FromStr for GgfGame {
+    type Err = ParseGameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let body = s
+            .trim()
+            .strip_prefix("(;")
+            .and_then(|s| s.strip_suffix(";)"))
+            .ok_or_else(|| format_error("(; で始まり ;) で終わっていません"))?;
+
+        let mut black = String::new();
+        let mut white = String::new();
+        let mut date = String::new();
+        let mut result = None;
+        let mut tags = vec![];
+        let mut start = (Osero::default(), Pattern::Black);
+        let mut moves = vec![];
+        for (key, value) in properties(body)? {
+            match key.as_str() {
+                "GM" if !value.eq_ignore_ascii_case("othello") => {
+                    return Err(format_error("オセロの棋譜ではありません"));
+                }
+                "GM" => {}
+                "PB" => black = value,
+                "PW" => white = value,
+                "DT" => date = value,
+                "RE" => result = Some(value),
+                "BO" => start = parse_board(&value)?,
+                "B" | "W" => {
+                    let with = if key == "B" {
+                        Pattern::Black
+                    } else {
+                        Pattern::White
+                    };
+                    let (mv, note) = parse_move(&value)?;
+                    moves.push((with, mv, note));
+                }
+                _ => tags.push((key, value)),
+            }
+        }
+
+        let mut game = Game::from_position(start.0, start.1);
+        let mut notes = vec![];
+        for (i, (with, mv, note)) in moves.into_iter().enumerate() {
+            // パスが書かれていなければ補う
+            if game.turn() != with && game.pass_if_forced() {
+                notes.push(MoveNote::default());
+            }
+            if game.turn() != with || !game.play(mv) {
+                return Err(ParseGameError::Illegal(i + 1, mv));
+            }
+            notes.push(note);
+        }
+
+        Ok(GgfGame {
+            record: Record {
+                black,
+                white,
+                level: None,
+                date,
+                game,
+            },
+            notes,
+            result,
+            tags,
+        })
+    }
+}
+
+impl fmt::Display for GgfGame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let record = &self.record;
+        write!(f, "(;GM[Othello]")?;
+        write!(f, "PB[{}]PW[{}]", record.black, record.white)?;
+        if !record.date.is_empty() {
+            write!(f, "DT[{}]", ggf_date(&record.date))?;
+        }
+        for (key, value) in self.tags.iter() {
+            write!(f, "{}[{}]", key, value)?;
+        }
+        let result = match record.game.result() {
+            Some(diff) => Some(format!("{:+}.000", diff).replace("+0.", "0.")),
+            None => self.result.clone(),
+        };
+        if let Some(result) = result {
+            write!(f, "RE[{}]", result)?;
+        }
+        let (start, turn) = record.game.start();
+        write!(f, "BO[{}]", format_board(start, turn))?;
+
+        let mut with = turn;
+        for (i, mv) in record.game.moves().into_iter().enumerate() {
+            let note = self.notes.get(i).copied().unwrap_or_default();
+            let key = if with == Pattern::White { "W" } else { "B" };
+            write!(f, "{}[{}]", key, format_move(mv, note))?;
+            with = with.fliped();
+        }
+        write!(f, ";)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 白が2回パスする、黒の 64 石勝ちの対局
+    const MOVES: &str = "c4c5e6e3c6d6f2f5d7e2g4g2g1e7e8pah2pad2";
+    const START: &str =
+        "8 -------- -------- -------- ---O*--- ---*O--- -------- -------- -------- *";
+
+    fn record(game: Game) -> Record {
+        Record {
+            black: "Alice".to_string(),
+            white: "osero".to_string(),
+            level: None,
+            date: String::new(),
+            game,
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut ggf = GgfGame::from(record(Game::from_transcript(MOVES).unwrap()));
+        ggf.notes[0] = MoveNote {
+            eval: Some(-1.25),
+            time: Some(0.5),
+        };
+        ggf.notes[1].time = Some(2.0);
+        ggf.tags.push(("PC".to_string(), "Tokyo".to_string()));
+        ggf.result = Some("+64.000".to_string());
+
+        let text = ggf.to_string();
+        assert!(text.contains("PC[Tokyo]RE[+64.000]"));
+        assert!(text.contains("B[c4/-1.25/0.50]W[c5//2.00]B[e6]"));
+        assert_eq!(text.parse(), Ok(ggf));
+    }
+
+    #[test]
+    fn inserts_passes() {
+        let text = format!(
+            "(;GM[Othello]PB[a]PW[b]BO[{}]B[C4]W[c5]B[e6]W[e3]B[c6]W[d6]B[f2]W[f5]\
+             B[d7]W[e2]B[g4]W[g2]B[g1]W[e7]B[e8]B[h2]B[d2];)",
+            START
+        );
+        let ggf: GgfGame = text.parse().unwrap();
+        assert_eq!(ggf.record.game.transcript(), MOVES);
+        assert_eq!(ggf.notes.len(), ggf.record.game.moves().len());
+        assert_eq!(ggf.record.game.result(), Some(64));
+    }
+
+    #[test]
+    fn reads_notes_and_side_to_move() {
+        let text = "(;GM[Othello]PB[a]PW[b]RE[+2.000:r]\
+                    BO[8 -------- -------- -------- ---O*--- ---***-- -------- -------- -------- O]\
+                    W[F6//1.2]B[e6/3.5];)";
+        let ggf: GgfGame = text.parse().unwrap();
+        assert_eq!(ggf.record.game.start().1, Pattern::White);
+        assert_eq!(ggf.record.game.transcript(), "f6e6");
+        assert_eq!(
+            ggf.notes,
+            [
+                MoveNote {
+                    eval: None,
+                    time: Some(1.2)
+                },
+                MoveNote {
+                    eval: Some(3.5),
+                    time: None
+                },
+            ]
+        );
+        // 終局していなければ RE はそのまま書く
+        assert_eq!(ggf.result.as_deref(), Some("+2.000:r"));
+        assert!(ggf.to_string().contains("RE[+2.000:r]"));
+    }
+
+    #[test]
+    fn rejects_bad_games() {
+        let game = |body: &str| format!("(;GM[Othello]PB[a]PW[b]{};)", body).parse::<GgfGame>();
+        assert!(matches!(
+            game("BO[10 ---------- *]"),
+            Err(ParseGameError::Format(_))
+        ));
+        assert!(matches!(
+            game(&START.replace(" *", " ?")),
+            Err(ParseGameError::Format(_))
+        ));
+        assert_eq!(
+            game(&format!("BO[{}]B[f5]B[d6]", START)),
+            Err(ParseGameError::Illegal(2, Move::Put(3, 5)))
+        );
+        assert!(matches!(game("B[z9]"), Err(ParseGameError::Move(_))));
+        assert!(matches!(game("B[f5"), Err(ParseGameError::Format(_))));
+        assert!(matches!(
+            "(;GM[Chess]PB[a]PW[b];)".parse::<GgfGame>(),
+            Err(ParseGameError::Format(_))
+        ));
+    }
+
+    #[test]
+    fn parses_several_games() {
+        let text = "1 (;GM[Othello]PB[a]PW[b]B[f5];)\n2 (;GM[Othello]PB[c]PW[d]B[d3]W[c5];)\n";
+        let games = GgfGame::parse_all(text).unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[1].record.black, "c");
+        assert_eq!(games[1].record.game.transcript(), "d3c5");
+        assert!(GgfGame::parse_all("(;GM[Othello]B[f5]").is_err());
+        assert_eq!(GgfGame::parse_all(""), Ok(vec![]));
+    }
+}
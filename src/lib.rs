@@ -0,0 +1,41 @@
+mod ai;
+mod bitboard;
+mod book;
+mod clock;
+mod endgame;
+mod eval;
+mod game;
+mod ggf;
+mod mcts;
+mod notation;
+mod osero;
+mod pattern;
+mod patterns;
+mod position;
+mod record;
+mod search;
+mod train;
+mod transposition;
+mod wthor;
+mod zobrist;
+
+pub use ai::POSITION_SCORE;
+pub use book::{BookMove, OpeningBook};
+pub use clock::Clock;
+pub use endgame::{EndgameResult, Outcome};
+pub use eval::{Evaluator, PositionalEvaluator, StandardEvaluator, Weight};
+pub use game::Game;
+pub use ggf::{GgfGame, MoveNote};
+pub use mcts::{Mcts, MctsResult, Playout};
+pub use notation::{Move, ParseMoveError, format_moves, parse_moves};
+pub use osero::Osero;
+pub use pattern::Pattern;
+pub use patterns::{PATTERN_NAMES, PatternEvaluator, WEIGHTS_VERSION};
+pub use position::ParsePositionError;
+pub use record::{ParseGameError, Record};
+pub use search::{Engine, SearchResult};
+pub use train::{
+    FitOptions, Sample, fit, read_samples, samples_from_game, self_play, write_samples,
+};
+pub use transposition::TranspositionTable;
+pub use wthor::{WthorDatabase, WthorGame, WthorHeader, read_wthor_names};
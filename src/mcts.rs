@@ -0,0 +1,319 @@
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::bitboard::{self, at_of};
+use crate::{Osero, POSITION_SCORE, Pattern};
+
+/// プレイアウトで手を選ぶ方法
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Playout {
+    /// 合法手から一様に選ぶ
+    Random,
+    /// POSITION_SCORE の高いマスほど選ばれやすくする
+    Light,
+}
+
+/// モンテカルロ木探索（UCT）で手を選ぶ AI。スレッドごとに別々の木を育てて、最後に根の訪問回数を合算する
+#[derive(Clone, Copy, Debug)]
+pub struct Mcts {
+    pub exploration: f64,
+    pub playout: Playout,
+}
+
+impl Default for Mcts {
+    fn default() -> Self {
+        Mcts {
+            exploration: std::f64::consts::SQRT_2,
+            playout: Playout::Light,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MctsResult {
+    pub best_move: Option<(usize, usize)>,
+    /// `best_move` を打ったときの勝率（引き分けは 0.5）
+    pub win_rate: f64,
+    pub playouts: u64,
+}
+
+impl Mcts {
+    pub fn with_playout(mut self, playout: Playout) -> Self {
+        self.playout = playout;
+        self
+    }
+
+    /// 全スレッド合わせておよそ `playouts` 回プレイアウトする
+    pub fn search(&self, osero: &Osero, with: Pattern, playouts: u64) -> MctsResult {
+        let threads = rayon::current_num_threads() as u64;
+        let per_thread = playouts.div_ceil(threads);
+        self.run(osero, with, threads, |done| done >= per_thread)
+    }
+
+    /// `limit` の間だけプレイアウトする
+    pub fn search_timed(&self, osero: &Osero, with: Pattern, limit: Duration) -> MctsResult {
+        let deadline = Instant::now() + limit;
+        let threads = rayon::current_num_threads() as u64;
+        // 毎回時計を見ると遅いので 64 回ごとに見る
+        self.run(osero, with, threads, |done| {
+            done & 63 == 0 && Instant::now() >= deadline
+        })
+    }
+
+    fn run(
+        &self,
+        osero: &Osero,
+        with: Pattern,
+        threads: u64,
+        finished: impl Fn(u64) -> bool + Sync,
+    ) -> MctsResult {
+        let moves = osero.moveable_bits(with);
+        if moves == 0 {
+            return MctsResult {
+                best_move: None,
+                win_rate: 0.0,
+                playouts: 0,
+            };
+        }
+        let (player, opponent) = osero.sides(with);
+
+        // 根の子ごとの (訪問回数, 勝ち数) をスレッドをまたいで合算する
+        let trees = (0..threads)
+            .into_par_iter()
+            .map(|_| {
+                let mut tree = Tree::new(player, opponent, self);
+                let mut done = 0;
+                while !finished(done) {
+                    tree.iterate();
+                    done += 1;
+                }
+                tree.root_stats()
+            })
+            .collect::<Vec<Vec<(u64, u64, f64)>>>();
+
+        let mut totals: Vec<(u64, u64, f64)> = vec![];
+        for stats in trees {
+            for (put, visits, wins) in stats {
+                match totals.iter_mut().find(|t| t.0 == put) {
+                    Some(total) => {
+                        total.1 += visits;
+                        total.2 += wins;
+                    }
+                    None => totals.push((put, visits, wins)),
+                }
+            }
+        }
+        let playouts = totals.iter().map(|t| t.1).sum();
+        // 一番多く訪れた手を選ぶ
+        let best = totals.iter().max_by_key(|t| t.1).copied();
+        match best {
+            Some((put, visits, wins)) => MctsResult {
+                best_move: Some(at_of(put.trailing_zeros())),
+                win_rate: wins / visits.max(1) as f64,
+                playouts,
+            },
+            None => MctsResult {
+                best_move: Some(at_of(moves.trailing_zeros())),
+                win_rate: 0.0,
+                playouts,
+            },
+        }
+    }
+}
+
+impl Osero {
+    /// MCTS で `playouts` 回ほどプレイアウトして手を選ぶ
+    pub fn best_move_mcts(&self, with: Pattern, playouts: u64) -> Option<(usize, usize)> {
+        Mcts::default().search(self, with, playouts).best_move
+    }
+}
+
+struct Node {
+    // 手番側と相手側の石
+    player: u64,
+    opponent: u64,
+    // 親からこの局面に来た手。パスなら 0
+    put: u64,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    // まだ子を作っていない手
+    untried: u64,
+    // パスしか無い局面で、まだパスの子を作っていない
+    untried_pass: bool,
+    visits: u64,
+    // この局面に来る手を打った側（親の手番側）から見た勝ち数
+    wins: f64,
+}
+
+impl Node {
+    fn new(player: u64, opponent: u64, put: u64, parent: Option<usize>) -> Self {
+        let untried = bitboard::legal_moves(player, opponent);
+        let untried_pass = untried == 0 && bitboard::legal_moves(opponent, player) != 0;
+        Node {
+            player,
+            opponent,
+            put,
+            parent,
+            children: vec![],
+            untried,
+            untried_pass,
+            visits: 0,
+            wins: 0.0,
+        }
+    }
+}
+
+struct Tree<'a> {
+    nodes: Vec<Node>,
+    mcts: &'a Mcts,
+    rng: SmallRng,
+}
+
+impl<'a> Tree<'a> {
+    fn new(player: u64, opponent: u64, mcts: &'a Mcts) -> Self {
+        Tree {
+            nodes: vec![Node::new(player, opponent, 0, None)],
+            mcts,
+            rng: SmallRng::from_rng(&mut rand::rng()),
+        }
+    }
+
+    fn iterate(&mut self) {
+        // 選択: 展開できる手が残っているか、終局の節点まで UCT でたどる
+        let mut current = 0;
+        while self.nodes[current].untried == 0
+            && !self.nodes[current].untried_pass
+            && !self.nodes[current].children.is_empty()
+        {
+            current = self.select_child(current);
+        }
+
+        // 展開
+        let node = &self.nodes[current];
+        let (player, opponent) = (node.player, node.opponent);
+        if node.untried != 0 {
+            let put = nth_bit(
+                node.untried,
+                self.rng.random_range(0..node.untried.count_ones()),
+            );
+            let flips = bitboard::flips(player, opponent, put);
+            let child = Node::new(opponent & !flips, player | put | flips, put, Some(current));
+            self.nodes[current].untried &= !put;
+            current = self.push_child(current, child);
+        } else if node.untried_pass {
+            self.nodes[current].untried_pass = false;
+            let child = Node::new(opponent, player, 0, Some(current));
+            current = self.push_child(current, child);
+        }
+
+        // シミュレーション: 葉の手番側から見た結果
+        let node = &self.nodes[current];
+        let result = self.playout(node.player, node.opponent);
+
+        // 逆伝播: 各節点にはその節点に来る手を打った側から見た勝ちを足す
+        let mut score = 1.0 - result;
+        let mut index = Some(current);
+        while let Some(i) = index {
+            let node = &mut self.nodes[i];
+            node.visits += 1;
+            node.wins += score;
+            score = 1.0 - score;
+            index = node.parent;
+        }
+    }
+
+    fn push_child(&mut self, parent: usize, child: Node) -> usize {
+        self.nodes.push(child);
+        let index = self.nodes.len() - 1;
+        self.nodes[parent].children.push(index);
+        index
+    }
+
+    fn select_child(&self, parent: usize) -> usize {
+        let log_visits = (self.nodes[parent].visits.max(1) as f64).ln();
+        let ucb = |&child: &usize| {
+            let node = &self.nodes[child];
+            let visits = node.visits.max(1) as f64;
+            node.wins / visits + self.mcts.exploration * (log_visits / visits).sqrt()
+        };
+        *self.nodes[parent]
+            .children
+            .iter()
+            .max_by(|a, b| ucb(a).total_cmp(&ucb(b)))
+            .expect("子のある節点だけを選ぶ")
+    }
+
+    /// 終局まで打って、最初の手番側が勝てば 1、引き分けなら 0.5、負ければ 0 を返す
+    fn playout(&mut self, mut player: u64, mut opponent: u64) -> f64 {
+        let mut flipped = false;
+        let mut passed = false;
+        loop {
+            let moves = bitboard::legal_moves(player, opponent);
+            if moves == 0 {
+                if passed {
+                    break;
+                }
+                passed = true;
+            } else {
+                passed = false;
+                let put = self.choose(moves);
+                let flips = bitboard::flips(player, opponent, put);
+                player |= put | flips;
+                opponent &= !flips;
+            }
+            (player, opponent) = (opponent, player);
+            flipped = !flipped;
+        }
+        if flipped {
+            (player, opponent) = (opponent, player);
+        }
+        match player.count_ones().cmp(&opponent.count_ones()) {
+            std::cmp::Ordering::Greater => 1.0,
+            std::cmp::Ordering::Less => 0.0,
+            std::cmp::Ordering::Equal => 0.5,
+        }
+    }
+
+    fn choose(&mut self, moves: u64) -> u64 {
+        match self.mcts.playout {
+            Playout::Random => nth_bit(moves, self.rng.random_range(0..moves.count_ones())),
+            Playout::Light => {
+                // 重みは POSITION_SCORE を正の数にずらしたもの
+                let weight = |at: (usize, usize)| (POSITION_SCORE[at.1 * 8 + at.0] + 60) as u32;
+                let total: u32 = bitboard::squares(moves).map(weight).sum();
+                let mut pick = self.rng.random_range(0..total);
+                for at in bitboard::squares(moves) {
+                    if pick < weight(at) {
+                        return bitboard::bit(at);
+                    }
+                    pick -= weight(at);
+                }
+                1 << moves.trailing_zeros()
+            }
+        }
+    }
+
+    /// 根の子ごとの (打った手, 訪問回数, 勝ち数)
+    fn root_stats(&self) -> Vec<(u64, u64, f64)> {
+        self.nodes[0]
+            .children
+            .iter()
+            .map(|&child| {
+                let node = &self.nodes[child];
+                (node.put, node.visits, node.wins)
+            })
+            .collect()
+    }
+}
+
+// n 番目（0 始まり）に立っているビット
+fn nth_bit(mut bits: u64, n: u32) -> u64 {
+    for _ in 0..n {
+        bits &= bits - 1;
+    }
+    bits & bits.wrapping_neg()
+}
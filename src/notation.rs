@@ -0,0 +1,187 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{EndgameResult, MctsResult, Osero, Pattern, SearchResult};
+
+/// 1手。マスは `a1`〜`h8` で書く。列 a〜h が x = 0〜7、行 1〜8 が y = 0〜7
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Move {
+    Put(usize, usize),
+    Pass,
+}
+
+impl Move {
+    /// 置くマスの (x, y)。パスなら None
+    pub fn at(&self) -> Option<(usize, usize)> {
+        match *self {
+            Move::Put(x, y) => Some((x, y)),
+            Move::Pass => None,
+        }
+    }
+
+    /// パスなら None の (x, y) から作る
+    pub fn from_at(at: Option<(usize, usize)>) -> Self {
+        at.map_or(Move::Pass, Move::from)
+    }
+}
+
+impl From<(usize, usize)> for Move {
+    fn from((x, y): (usize, usize)) -> Self {
+        Move::Put(x, y)
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Move::Put(x, y) => write!(f, "{}{}", (b'a' + x as u8) as char, y + 1),
+            Move::Pass => write!(f, "pass"),
+        }
+    }
+}
+
+/// `Move` として読めない文字列
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseMoveError(String);
+
+impl fmt::Display for ParseMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} は手として読めません（例: f5、pass）", self.0)
+    }
+}
+
+impl std::error::Error for ParseMoveError {}
+
+impl FromStr for Move {
+    type Err = ParseMoveError;
+
+    /// `f5`・`F5` のようなマスか、パスを表す `pass`・`pa`・`--` を読む
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.trim().to_ascii_lowercase();
+        match lower.as_bytes() {
+            b"pass" | b"pa" | b"ps" | b"--" => Ok(Move::Pass),
+            &[column @ b'a'..=b'h', row @ b'1'..=b'8'] => {
+                Ok(Move::Put((column - b'a') as usize, (row - b'1') as usize))
+            }
+            _ => Err(ParseMoveError(s.to_string())),
+        }
+    }
+}
+
+/// `f5d6c3` のように続けて書いた手順を読む。パスは `pa` か `--` で書ける
+pub fn parse_moves(sequence: &str) -> Result<Vec<Move>, ParseMoveError> {
+    let chars: Vec<char> = sequence.trim().chars().collect();
+    chars
+        .chunks(2)
+        .map(|pair| pair.iter().collect::<String>().parse())
+        .collect()
+}
+
+/// 手順を `f5d6c3` のように続けて書く。パスは `pa`
+pub fn format_moves(moves: &[Move]) -> String {
+    moves
+        .iter()
+        .map(|mv| match mv {
+            Move::Pass => "pa".to_string(),
+            mv => mv.to_string(),
+        })
+        .collect()
+}
+
+impl Osero {
+    /// `with` が打てる手。置けるマスがなくても相手が置けるならパスだけを返し、終局していれば空
+    pub fn legal_moves(&self, with: Pattern) -> Vec<Move> {
+        let moves: Vec<Move> = self
+            .get_moveable_points(with)
+            .into_iter()
+            .map(Move::from)
+            .collect();
+        if moves.is_empty() && self.is_moveable_with(with.fliped()) {
+            vec![Move::Pass]
+        } else {
+            moves
+        }
+    }
+
+    pub fn is_legal(&self, with: Pattern, mv: Move) -> bool {
+        match mv {
+            Move::Put(x, y) => self.is_putabele(with, (x, y)),
+            Move::Pass => self.legal_moves(with) == [Move::Pass],
+        }
+    }
+
+    /// `mv` を打つ。打てない手なら何もせずに false を返す
+    pub fn play(&mut self, with: Pattern, mv: Move) -> bool {
+        if !self.is_legal(with, mv) {
+            return false;
+        }
+        if let Move::Put(x, y) = mv {
+            self.put(with, (x, y));
+        }
+        true
+    }
+}
+
+impl SearchResult {
+    /// 選んだ手。置ける手がなければパス
+    pub fn best(&self) -> Move {
+        Move::from_at(self.best_move)
+    }
+}
+
+impl EndgameResult {
+    /// 選んだ手。置ける手がなければパス
+    pub fn best(&self) -> Move {
+        Move::from_at(self.best_move)
+    }
+}
+
+impl MctsResult {
+    /// 選んだ手。置ける手がなければパス
+    pub fn best(&self) -> Move {
+        Move::from_at(self.best_move)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_moves() {
+        assert_eq!("a1".parse(), Ok(Move::Put(0, 0)));
+        assert_eq!("F5".parse(), Ok(Move::Put(5, 4)));
+        assert_eq!(" h8 ".parse(), Ok(Move::Put(7, 7)));
+        for pass in ["pass", "PA", "ps", "--"] {
+            assert_eq!(pass.parse(), Ok(Move::Pass), "{}", pass);
+        }
+        for bad in ["", "i1", "a0", "a9", "f55", "5f", "p"] {
+            assert!(bad.parse::<Move>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn displays_moves() {
+        assert_eq!(Move::Put(0, 0).to_string(), "a1");
+        assert_eq!(Move::Put(7, 7).to_string(), "h8");
+        assert_eq!(Move::Pass.to_string(), "pass");
+        for x in 0..8 {
+            for y in 0..8 {
+                assert_eq!(Move::Put(x, y).to_string().parse(), Ok(Move::Put(x, y)));
+            }
+        }
+    }
+
+    #[test]
+    fn parses_and_formats_sequences() {
+        let moves = vec![Move::Put(5, 4), Move::Pass, Move::Put(3, 5)];
+        assert_eq!(parse_moves("f5pad6"), Ok(moves.clone()));
+        assert_eq!(parse_moves("F5--D6"), Ok(moves.clone()));
+        assert_eq!(format_moves(&moves), "f5pad6");
+        assert_eq!(parse_moves(""), Ok(vec![]));
+        assert_eq!(format_moves(&[]), "");
+        // 2文字ずつに分けられなければ読めない
+        assert!(parse_moves("f5d").is_err());
+        assert!(parse_moves("f5z9").is_err());
+    }
+}
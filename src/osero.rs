@@ -0,0 +1,197 @@
+use crate::Pattern;
+use crate::bitboard::{self, bit};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Osero {
+    black: u64,
+    white: u64,
+}
+
+/// 標準の初期配置。d4・e5 が白、e4・d5 が黒で、黒の最初の手は d3・c4・f5・e6 のどれか。
+/// WTHOR や GGF の棋譜、NBoard・GTP の手、定石の手順はどれもこの配置で書かれているので、
+/// 座標を付け替えずにそのまま読めるようにそろえている
+impl Default for Osero {
+    fn default() -> Self {
+        let mut osero = Osero { black: 0, white: 0 };
+        osero.set(Pattern::White, (3, 3));
+        osero.set(Pattern::Black, (4, 3));
+        osero.set(Pattern::Black, (3, 4));
+        osero.set(Pattern::White, (4, 4));
+        osero
+    }
+}
+
+impl From<[Pattern; 64]> for Osero {
+    fn from(cells: [Pattern; 64]) -> Self {
+        let mut osero = Osero { black: 0, white: 0 };
+        for (i, point) in cells.into_iter().enumerate() {
+            osero.set(point, bitboard::at_of(i as u32));
+        }
+        osero
+    }
+}
+
+impl Osero {
+    pub fn from_bitboards(black: u64, white: u64) -> Self {
+        Osero {
+            black,
+            white: white & !black,
+        }
+    }
+
+    /// 手番側の石と相手側の石の組から作る
+    pub(crate) fn from_sides(with: Pattern, player: u64, opponent: u64) -> Self {
+        match with {
+            Pattern::White => Osero::from_bitboards(opponent, player),
+            _ => Osero::from_bitboards(player, opponent),
+        }
+    }
+
+    pub fn bitboard(&self, with: Pattern) -> u64 {
+        match with {
+            Pattern::Black => self.black,
+            Pattern::White => self.white,
+            Pattern::None => !(self.black | self.white),
+        }
+    }
+
+    /// 手番側の石と相手側の石の組
+    pub(crate) fn sides(&self, with: Pattern) -> (u64, u64) {
+        match with {
+            Pattern::Black => (self.black, self.white),
+            Pattern::White => (self.white, self.black),
+            Pattern::None => (0, 0),
+        }
+    }
+
+    pub fn cells(&self) -> [Pattern; 64] {
+        let mut cells = [Pattern::None; 64];
+        for (i, cell) in cells.iter_mut().enumerate() {
+            *cell = self.get(bitboard::at_of(i as u32)).unwrap_or_default();
+        }
+        cells
+    }
+
+    pub fn which_win(&self) -> Option<Pattern> {
+        let (black, white, _) = self.many();
+        if black > white {
+            Some(Pattern::Black)
+        } else if black < white {
+            Some(Pattern::White)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        let no_empty_cells = self.black | self.white == u64::MAX;
+        let no_moves_black = !self.is_moveable_with(Pattern::Black);
+        let no_moves_white = !self.is_moveable_with(Pattern::White);
+
+        no_empty_cells || (no_moves_black && no_moves_white)
+    }
+
+    pub fn many(&self) -> (usize, usize, usize) {
+        let black = self.black.count_ones() as usize;
+        let white = self.white.count_ones() as usize;
+        (black, white, 64 - black - white)
+    }
+
+    pub fn express(&self) -> String {
+        let mut result = String::new();
+        let mut y = 1;
+
+        // 横軸ラベル（a〜h）
+        result.push_str("  a b c d e f g h\n");
+
+        for (i, point) in self.cells().iter().enumerate() {
+            if i % 8 == 0 {
+                result.push_str(&format!("{} ", y)); // 縦軸ラベル（1〜8）
+                y += 1;
+            }
+
+            match point {
+                Pattern::None => result.push_str(". "),
+                Pattern::Black => result.push_str("X "),
+                Pattern::White => result.push_str("O "),
+            }
+
+            if i % 8 == 7 {
+                result.push('\n');
+            }
+        }
+
+        result
+    }
+
+    pub fn is_putabele(&self, with: Pattern, at: (usize, usize)) -> bool {
+        at.0 < 8 && at.1 < 8 && self.moveable_bits(with) & bit(at) != 0
+    }
+
+    pub fn put(&mut self, with: Pattern, at: (usize, usize)) {
+        if at.0 >= 8 || at.1 >= 8 {
+            return;
+        }
+        let put = bit(at);
+        let (player, opponent) = self.sides(with);
+        let flips = bitboard::flips(player, opponent, put);
+        // 1枚も返せないなら置けない
+        if flips == 0 {
+            return;
+        }
+
+        let (player, opponent) = (player | put | flips, opponent & !flips);
+        match with {
+            Pattern::Black => (self.black, self.white) = (player, opponent),
+            Pattern::White => (self.white, self.black) = (player, opponent),
+            Pattern::None => {}
+        }
+    }
+
+    pub fn get(&self, at: (usize, usize)) -> Option<Pattern> {
+        if at.0 < 8 && at.1 < 8 {
+            let b = bit(at);
+            if self.black & b != 0 {
+                Some(Pattern::Black)
+            } else if self.white & b != 0 {
+                Some(Pattern::White)
+            } else {
+                Some(Pattern::None)
+            }
+        } else {
+            None
+        }
+    }
+
+    pub fn set(&mut self, with: Pattern, at: (usize, usize)) -> bool {
+        if at.0 < 8 && at.1 < 8 {
+            let b = bit(at);
+            self.black &= !b;
+            self.white &= !b;
+            match with {
+                Pattern::Black => self.black |= b,
+                Pattern::White => self.white |= b,
+                Pattern::None => {}
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn moveable_bits(&self, with: Pattern) -> u64 {
+        if with == Pattern::None {
+            return 0;
+        }
+        let (player, opponent) = self.sides(with);
+        bitboard::legal_moves(player, opponent)
+    }
+
+    pub fn is_moveable_with(&self, with: Pattern) -> bool {
+        self.moveable_bits(with) != 0
+    }
+
+    pub fn get_moveable_points(&self, with: Pattern) -> Vec<(usize, usize)> {
+        bitboard::squares(self.moveable_bits(with)).collect()
+    }
+}
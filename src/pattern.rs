@@ -0,0 +1,35 @@
+use std::fmt::Display;
+
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash, Debug)]
+pub enum Pattern {
+    Black,
+    White,
+    #[default]
+    None,
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pattern::Black => {
+                write!(f, "Black")
+            }
+            Pattern::White => {
+                write!(f, "White")
+            }
+            Pattern::None => {
+                write!(f, "None")
+            }
+        }
+    }
+}
+
+impl Pattern {
+    pub fn fliped(&self) -> Pattern {
+        match self {
+            Pattern::White => Pattern::Black,
+            Pattern::Black => Pattern::White,
+            Pattern::None => Pattern::None,
+        }
+    }
+}
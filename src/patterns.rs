@@ -0,0 +1,298 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::LazyLock;
+
+use crate::eval::Evaluator;
+use crate::search::FINAL_SCORE_UNIT;
+use crate::{Osero, Pattern};
+
+const MAGIC: &[u8; 8] = b"OSEROPAT";
+pub const WEIGHTS_VERSION: u32 = 1;
+
+// 重みは石1枚を UNIT として持つ
+pub(crate) const UNIT: i32 = 32;
+
+/// 評価に使うパターンの種類。ファイルの中の重みもこの順に並ぶ
+pub const PATTERN_NAMES: [&str; 11] = [
+    "edge+2X",
+    "corner2x5",
+    "corner3x3",
+    "line2",
+    "line3",
+    "line4",
+    "diag8",
+    "diag7",
+    "diag6",
+    "diag5",
+    "diag4",
+];
+
+// 各パターンの元になる形。(x, y) の並び
+fn base_shapes() -> [Vec<(usize, usize)>; 11] {
+    [
+        (0..8).map(|x| (x, 0)).chain([(1, 1), (6, 1)]).collect(),
+        (0..5)
+            .map(|x| (x, 0))
+            .chain((0..5).map(|x| (x, 1)))
+            .collect(),
+        (0..9).map(|i| (i % 3, i / 3)).collect(),
+        (0..8).map(|x| (x, 1)).collect(),
+        (0..8).map(|x| (x, 2)).collect(),
+        (0..8).map(|x| (x, 3)).collect(),
+        (0..8).map(|i| (i, i)).collect(),
+        (0..7).map(|i| (i, i + 1)).collect(),
+        (0..6).map(|i| (i, i + 2)).collect(),
+        (0..5).map(|i| (i, i + 3)).collect(),
+        (0..4).map(|i| (i, i + 4)).collect(),
+    ]
+}
+
+type Symmetry = fn((usize, usize)) -> (usize, usize);
+
+pub(crate) struct Shape {
+    pub(crate) len: usize,
+    // 回転・反転で盤上に現れるすべての位置。マスの番号の並び
+    pub(crate) instances: Vec<Vec<usize>>,
+}
+
+pub(crate) static SHAPES: LazyLock<Vec<Shape>> = LazyLock::new(|| {
+    let symmetries: [Symmetry; 8] = [
+        |(x, y)| (x, y),
+        |(x, y)| (7 - x, y),
+        |(x, y)| (x, 7 - y),
+        |(x, y)| (7 - x, 7 - y),
+        |(x, y)| (y, x),
+        |(x, y)| (7 - y, x),
+        |(x, y)| (y, 7 - x),
+        |(x, y)| (7 - y, 7 - x),
+    ];
+    base_shapes()
+        .into_iter()
+        .map(|base| {
+            let mut instances: Vec<Vec<usize>> = vec![];
+            for symmetry in symmetries {
+                let squares: Vec<usize> = base
+                    .iter()
+                    .map(|at| symmetry(*at))
+                    .map(|(x, y)| y * 8 + x)
+                    .collect();
+                // 同じマスの組み合わせになるものは1つにまとめる
+                let mut sorted = squares.clone();
+                sorted.sort();
+                let duplicated = instances.iter().any(|other| {
+                    let mut other = other.clone();
+                    other.sort();
+                    other == sorted
+                });
+                if !duplicated {
+                    instances.push(squares);
+                }
+            }
+            Shape {
+                len: base.len(),
+                instances,
+            }
+        })
+        .collect()
+});
+
+/// 手番側から見たパターンの番号を (パターンの種類, 番号) で列挙する。
+/// マスの値は 空き = 0, 手番側 = 1, 相手 = 2 の3進数
+pub(crate) fn pattern_indices(player: u64, opponent: u64) -> impl Iterator<Item = (usize, usize)> {
+    SHAPES.iter().enumerate().flat_map(move |(kind, shape)| {
+        shape.instances.iter().map(move |squares| {
+            let index = squares.iter().fold(0, |index, &square| {
+                let value = if player >> square & 1 != 0 {
+                    1
+                } else if opponent >> square & 1 != 0 {
+                    2
+                } else {
+                    0
+                };
+                index * 3 + value
+            });
+            (kind, index)
+        })
+    })
+}
+
+/// 空きマスの数から序盤〜終盤のどの重みを使うかを決める
+pub(crate) fn phase_of(empties: usize, phases: usize) -> usize {
+    (60 - empties.min(60)) * phases / 61
+}
+
+/// パターンごとの重みを局面の進み具合（phase）別に持つ評価関数。重みはファイルから読める
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PatternEvaluator {
+    // [phase][パターンの種類][番号]
+    weights: Vec<Vec<Vec<i16>>>,
+}
+
+impl PatternEvaluator {
+    pub const DEFAULT_PHASES: usize = 6;
+    /// 空きマスの数より細かく分けても意味がない
+    pub const MAX_PHASES: usize = 60;
+
+    /// 重みがすべて 0 の評価関数を作る
+    pub fn new(phases: usize) -> Self {
+        let weights = (0..phases.max(1))
+            .map(|_| {
+                SHAPES
+                    .iter()
+                    .map(|shape| vec![0; 3usize.pow(shape.len as u32)])
+                    .collect()
+            })
+            .collect();
+        PatternEvaluator { weights }
+    }
+
+    pub(crate) fn from_weights(weights: Vec<Vec<Vec<i16>>>) -> Self {
+        PatternEvaluator { weights }
+    }
+
+    pub fn phases(&self) -> usize {
+        self.weights.len()
+    }
+
+    /// 石1枚を UNIT とした単位での評価
+    fn raw(&self, player: u64, opponent: u64) -> i32 {
+        let empties = (!(player | opponent)).count_ones() as usize;
+        let phase = &self.weights[phase_of(empties, self.phases())];
+        pattern_indices(player, opponent)
+            .map(|(kind, index)| phase[kind][index] as i32)
+            .sum()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::read_from(&mut BufReader::new(File::open(path)?))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    /// ファイルの形式: "OSEROPAT", バージョン (u32), phase の数 (u32),
+    /// そのあと phase ごと・パターンの種類ごとに 3^マス数 個の i16。すべてリトルエンディアン
+    pub fn read_from(reader: &mut impl Read) -> io::Result<Self> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("重みのファイルではありません".to_string()));
+        }
+        let mut word = [0; 4];
+        reader.read_exact(&mut word)?;
+        let version = u32::from_le_bytes(word);
+        if version != WEIGHTS_VERSION {
+            return Err(invalid(format!(
+                "重みのファイルのバージョン {} には対応していません",
+                version
+            )));
+        }
+        reader.read_exact(&mut word)?;
+        let phases = u32::from_le_bytes(word) as usize;
+        if !(1..=Self::MAX_PHASES).contains(&phases) {
+            return Err(invalid(format!("phase の数 {} は無効です", phases)));
+        }
+
+        let mut evaluator = PatternEvaluator::new(phases);
+        let mut half = [0; 2];
+        for phase in evaluator.weights.iter_mut() {
+            for table in phase.iter_mut() {
+                for weight in table.iter_mut() {
+                    reader.read_exact(&mut half)?;
+                    *weight = i16::from_le_bytes(half);
+                }
+            }
+        }
+        Ok(evaluator)
+    }
+
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&WEIGHTS_VERSION.to_le_bytes())?;
+        writer.write_all(&(self.phases() as u32).to_le_bytes())?;
+        for phase in self.weights.iter() {
+            for table in phase.iter() {
+                for weight in table.iter() {
+                    writer.write_all(&weight.to_le_bytes())?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for PatternEvaluator {
+    fn default() -> Self {
+        PatternEvaluator::new(Self::DEFAULT_PHASES)
+    }
+}
+
+impl Evaluator for PatternEvaluator {
+    fn evaluate(&self, osero: &Osero, with: Pattern) -> i32 {
+        let (player, opponent) = osero.sides(with);
+        self.raw(player, opponent) * FINAL_SCORE_UNIT / UNIT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(version: u32, phases: u32) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(version.to_le_bytes());
+        bytes.extend(phases.to_le_bytes());
+        bytes
+    }
+
+    fn read(bytes: &[u8]) -> io::Result<PatternEvaluator> {
+        PatternEvaluator::read_from(&mut &bytes[..])
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut evaluator = PatternEvaluator::new(3);
+        for (p, phase) in evaluator.weights.iter_mut().enumerate() {
+            for (k, table) in phase.iter_mut().enumerate() {
+                for (i, weight) in table.iter_mut().enumerate() {
+                    *weight = (i as i16)
+                        .wrapping_mul(31)
+                        .wrapping_sub((p * 100 + k) as i16);
+                }
+            }
+        }
+        let mut bytes = vec![];
+        evaluator.write_to(&mut bytes).unwrap();
+        assert_eq!(read(&bytes).unwrap(), evaluator);
+
+        // 途中で切れていれば読めない
+        bytes.pop();
+        assert_eq!(
+            read(&bytes).unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn rejects_bad_headers() {
+        let mut bytes = header(WEIGHTS_VERSION, 1);
+        bytes[0] = b'X';
+        assert_eq!(read(&bytes).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        let bytes = header(WEIGHTS_VERSION + 1, 1);
+        assert_eq!(read(&bytes).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        for phases in [0, PatternEvaluator::MAX_PHASES as u32 + 1, u32::MAX] {
+            let bytes = header(WEIGHTS_VERSION, phases);
+            assert_eq!(
+                read(&bytes).unwrap_err().kind(),
+                io::ErrorKind::InvalidData,
+                "{}",
+                phases
+            );
+        }
+    }
+}
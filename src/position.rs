@@ -0,0 +1,133 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Osero, Pattern};
+
+/// 局面の文字列が読めなかった理由
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParsePositionError(String);
+
+impl fmt::Display for ParsePositionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ParsePositionError {}
+
+/// a1, b1, ..., h8 の順に 64 マスを `X`（黒）・`O`（白）・`-`（空き）で書く
+impl fmt::Display for Osero {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for cell in self.cells() {
+            let c = match cell {
+                Pattern::Black => 'X',
+                Pattern::White => 'O',
+                Pattern::None => '-',
+            };
+            write!(f, "{}", c)?;
+        }
+        Ok(())
+    }
+}
+
+/// 64 マスを読む。後ろに手番が書いてあっても無視する
+impl FromStr for Osero {
+    type Err = ParsePositionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Osero::parse_position(s).map(|(osero, _)| osero)
+    }
+}
+
+impl Osero {
+    /// 64 マスのあとに空白と手番（`X` か `O`）を書いた1行。obf 形式と同じ
+    pub fn position_string(&self, with: Pattern) -> String {
+        let side = if with == Pattern::White { 'O' } else { 'X' };
+        format!("{} {}", self, side)
+    }
+
+    /// `position_string` の形の1行を読む。マスは `X`・`*`・`B` が黒、`O`・`W` が白、`-`・`.`・`_` が空き。
+    /// 空白は読み飛ばし、手番を省くと黒番、`;` から後ろ（obf の評価値など）は無視する
+    pub fn parse_position(s: &str) -> Result<(Osero, Pattern), ParsePositionError> {
+        let s = s.split(';').next().unwrap_or_default();
+        let chars: Vec<char> = s.chars().filter(|c| !c.is_whitespace()).collect();
+        let (cells, side) = match chars.len() {
+            64 => (&chars[..], None),
+            65 => (&chars[..64], Some(chars[64])),
+            n => {
+                return Err(ParsePositionError(format!(
+                    "局面は 64 マスと手番で書きます（{} 文字あります）",
+                    n
+                )));
+            }
+        };
+
+        let mut board = [Pattern::None; 64];
+        for (i, (cell, &c)) in board.iter_mut().zip(cells).enumerate() {
+            *cell = match c.to_ascii_uppercase() {
+                'X' | '*' | 'B' => Pattern::Black,
+                'O' | 'W' => Pattern::White,
+                '-' | '.' | '_' => Pattern::None,
+                _ => {
+                    return Err(ParsePositionError(format!(
+                        "{}文字目の {} はマスとして読めません",
+                        i + 1,
+                        c
+                    )));
+                }
+            };
+        }
+        let with = match side.map(|c| c.to_ascii_uppercase()) {
+            None | Some('X' | '*' | 'B') => Pattern::Black,
+            Some('O' | 'W') => Pattern::White,
+            Some(c) => {
+                return Err(ParsePositionError(format!("手番の {} が読めません", c)));
+            }
+        };
+        Ok((Osero::from(board), with))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: &str = "---------------------------OX------XO--------------------------- X";
+
+    #[test]
+    fn writes_start_position() {
+        assert_eq!(Osero::default().position_string(Pattern::Black), START);
+    }
+
+    #[test]
+    fn position_string_round_trips() {
+        let mut osero = Osero::default();
+        osero.put(Pattern::Black, (5, 4));
+        for with in [Pattern::Black, Pattern::White] {
+            let s = osero.position_string(with);
+            assert_eq!(Osero::parse_position(&s), Ok((osero, with)));
+        }
+        assert_eq!(osero.to_string().parse(), Ok(osero));
+    }
+
+    #[test]
+    fn reads_other_notations() {
+        let s = "........ ........ ........ ...wb... ...bw... ........ ........ ........ w ; 12";
+        assert_eq!(
+            Osero::parse_position(s),
+            Ok((Osero::default(), Pattern::White))
+        );
+        assert_eq!(
+            Osero::parse_position(&START[..64]),
+            Ok((Osero::default(), Pattern::Black))
+        );
+    }
+
+    #[test]
+    fn rejects_bad_positions() {
+        assert!(Osero::parse_position(&START[..63]).is_err());
+        assert!(Osero::parse_position(&format!("{}-", START)).is_err());
+        assert!(Osero::parse_position(&START.replacen('-', "?", 1)).is_err());
+        assert!(Osero::parse_position(&START.replace(" X", " Z")).is_err());
+    }
+}
@@ -0,0 +1,297 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::endgame::final_difference;
+use crate::{Game, Move, Osero, ParseMoveError, Pattern, format_moves, parse_moves};
+
+/// 棋譜を読めなかった理由
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ParseGameError {
+    Move(ParseMoveError),
+    /// 手順の n 手目（1 始まり）が打てない
+    Illegal(usize, Move),
+    /// 棋譜のファイルの n 行目が読めない
+    Line(usize),
+    /// 棋譜の形式が正しくない
+    Format(String),
+}
+
+impl fmt::Display for ParseGameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseGameError::Move(e) => write!(f, "{}", e),
+            ParseGameError::Illegal(n, mv) => write!(f, "{}手目の {} は打てません", n, mv),
+            ParseGameError::Line(n) => write!(f, "{}行目が読めません", n),
+            ParseGameError::Format(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for ParseGameError {}
+
+impl From<ParseMoveError> for ParseGameError {
+    fn from(e: ParseMoveError) -> Self {
+        ParseGameError::Move(e)
+    }
+}
+
+impl Game {
+    /// 打った手を `f5d6c3` のように続けて書く。パスは `pa`
+    pub fn transcript(&self) -> String {
+        format_moves(&self.moves())
+    }
+
+    /// 初期局面から `transcript` の手順を打った対局を作る。
+    /// パスは書いても書かなくてもよく、書いていなければ置けない側は自動でパスする
+    pub fn from_transcript(transcript: &str) -> Result<Game, ParseGameError> {
+        let mut game = Game::new();
+        game.play_transcript(transcript)?;
+        Ok(game)
+    }
+
+    /// 今の局面から `transcript` の手順を続けて打つ。打てない手があればそこで止める
+    pub fn play_transcript(&mut self, transcript: &str) -> Result<(), ParseGameError> {
+        let offset = self.moves().len();
+        for (i, mv) in parse_moves(transcript)?.into_iter().enumerate() {
+            if mv != Move::Pass {
+                self.pass_if_forced();
+            }
+            if !self.play(mv) {
+                return Err(ParseGameError::Illegal(offset + i + 1, mv));
+            }
+        }
+        Ok(())
+    }
+
+    /// 終局していれば黒から見た石差。空きマスは勝った側に数える
+    pub fn result(&self) -> Option<i32> {
+        if !self.is_finished() {
+            return None;
+        }
+        let osero = self.board();
+        Some(final_difference(
+            osero.bitboard(Pattern::Black),
+            osero.bitboard(Pattern::White),
+        ))
+    }
+}
+
+/// 対局者やレベルなどの情報つきの棋譜
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Record {
+    pub black: String,
+    pub white: String,
+    /// AI のレベル。時間で考えた対局なら None
+    pub level: Option<usize>,
+    /// 対局した日時（UTC）
+    pub date: String,
+    pub game: Game,
+}
+
+impl Record {
+    /// 今の日時で棋譜を作る
+    pub fn new(game: Game, black: &str, white: &str, level: Option<usize>) -> Self {
+        Record {
+            black: black.to_string(),
+            white: white.to_string(),
+            level,
+            date: format_date(SystemTime::now()),
+            game,
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        fs::read_to_string(path)?
+            .parse()
+            .map_err(|e: ParseGameError| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+/// 1行に1項目「名前: 値」で書く。Result は読むときには使わず、手順から求め直す。
+/// 初期局面以外から始めた対局は Position に開始局面を書く
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Black: {}", self.black)?;
+        writeln!(f, "White: {}", self.white)?;
+        if let Some(level) = self.level {
+            writeln!(f, "Level: {}", level)?;
+        }
+        writeln!(f, "Date: {}", self.date)?;
+        let result = match self.game.result() {
+            Some(diff) if diff > 0 => format!("{} +{}", Pattern::Black, diff),
+            Some(diff) if diff < 0 => format!("{} +{}", Pattern::White, -diff),
+            Some(_) => "Draw".to_string(),
+            None => "*".to_string(),
+        };
+        writeln!(f, "Result: {}", result)?;
+        let (start, turn) = self.game.start();
+        if (*start, turn) != (Osero::default(), Pattern::Black) {
+            writeln!(f, "Position: {}", start.position_string(turn))?;
+        }
+        writeln!(f, "Moves: {}", self.game.transcript())
+    }
+}
+
+impl FromStr for Record {
+    type Err = ParseGameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut record = Record {
+            black: String::new(),
+            white: String::new(),
+            level: None,
+            date: String::new(),
+            game: Game::new(),
+        };
+        let mut moves = "";
+        for (i, line) in s.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line.split_once(':').ok_or(ParseGameError::Line(i + 1))?;
+            let value = value.trim();
+            match key.trim() {
+                "Black" => record.black = value.to_string(),
+                "White" => record.white = value.to_string(),
+                "Level" => {
+                    record.level = Some(value.parse().map_err(|_| ParseGameError::Line(i + 1))?)
+                }
+                "Date" => record.date = value.to_string(),
+                "Position" => {
+                    let (osero, turn) = Osero::parse_position(value)
+                        .map_err(|e| ParseGameError::Format(e.to_string()))?;
+                    record.game = Game::from_position(osero, turn);
+                }
+                "Moves" => moves = value,
+                // Result などの知らない項目は読み飛ばす
+                _ => {}
+            }
+        }
+        record.game.play_transcript(moves)?;
+        Ok(record)
+    }
+}
+
+// "2024-01-02 03:04:05 UTC" の形にする
+fn format_date(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, rest) = (seconds / 86400, seconds % 86400);
+
+    // 1970-01-01 からの日数を年月日にする（グレゴリオ暦）
+    let days = days as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        rest / 3600,
+        rest % 3600 / 60,
+        rest % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use rand::seq::IndexedRandom;
+
+    use super::*;
+
+    // 乱数で終局まで打った対局。途中にパスのあるものを選ぶ
+    fn game_with_pass() -> Game {
+        let mut rng = StdRng::seed_from_u64(0);
+        loop {
+            let mut game = Game::new();
+            while !game.is_finished() {
+                let mv = *game.legal_moves().choose(&mut rng).unwrap();
+                game.play(mv);
+            }
+            if game.moves().contains(&Move::Pass) {
+                return game;
+            }
+        }
+    }
+
+    #[test]
+    fn transcript_round_trips() {
+        let game = game_with_pass();
+        let transcript = game.transcript();
+        assert_eq!(Game::from_transcript(&transcript), Ok(game.clone()));
+
+        // パスを省いても同じ対局になる
+        let without_pass = transcript.replace("pa", "");
+        assert_eq!(Game::from_transcript(&without_pass), Ok(game.clone()));
+        assert!(game.result().is_some());
+    }
+
+    #[test]
+    fn transcript_rejects_illegal_moves() {
+        assert_eq!(
+            Game::from_transcript("f5f5"),
+            Err(ParseGameError::Illegal(2, Move::Put(5, 4)))
+        );
+        assert!(matches!(
+            Game::from_transcript("f5z9"),
+            Err(ParseGameError::Move(_))
+        ));
+    }
+
+    #[test]
+    fn record_round_trips() {
+        let record = Record {
+            black: "Alice".to_string(),
+            white: "osero".to_string(),
+            level: Some(5),
+            date: "2024-01-02 03:04:05 UTC".to_string(),
+            game: game_with_pass(),
+        };
+        assert_eq!(record.to_string().parse(), Ok(record));
+    }
+
+    #[test]
+    fn record_round_trips_from_position() {
+        let mut osero = Osero::default();
+        osero.put(Pattern::Black, (5, 4));
+        let mut game = Game::from_position(osero, Pattern::White);
+        game.play_transcript("f6e6").unwrap();
+        let record = Record {
+            black: "Black".to_string(),
+            white: "White".to_string(),
+            level: None,
+            date: String::new(),
+            game,
+        };
+        let text = record.to_string();
+        assert!(text.contains("Position: "));
+        assert_eq!(text.parse(), Ok(record));
+    }
+
+    #[test]
+    fn formats_dates() {
+        let time = UNIX_EPOCH + std::time::Duration::from_secs(951_825_845);
+        assert_eq!(format_date(time), "2000-02-29 12:04:05 UTC");
+    }
+}
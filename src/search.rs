@@ -0,0 +1,651 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+use crate::bitboard::{self, bit};
+use crate::book::OpeningBook;
+use crate::endgame;
+use crate::eval::{Evaluator, StandardEvaluator};
+use crate::transposition::{Bound, Entry, TranspositionTable};
+use crate::zobrist::KEYS;
+use crate::{Move, Osero, POSITION_SCORE, Pattern};
+
+pub(crate) const INFINITY: i32 = 1_000_000;
+// 終局時の石差1枚あたりの評価値。どの評価値よりも大きくしておく
+pub(crate) const FINAL_SCORE_UNIT: i32 = 1000;
+
+// POSITION_SCORE の高い順に並べたマス。合法手はこの順で試す
+static SQUARE_ORDER: LazyLock<[u32; 64]> = LazyLock::new(|| {
+    let mut order: [u32; 64] = std::array::from_fn(|i| i as u32);
+    order.sort_by_key(|&i| -POSITION_SCORE[i as usize]);
+    order
+});
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchResult {
+    pub best_move: Option<(usize, usize)>,
+    /// `with` から見た評価値
+    pub score: i32,
+    /// 読み切った深さ。定石の手なら 0
+    pub depth: usize,
+    pub nodes: u64,
+    /// 終局まで読み切った結果なら true
+    pub exact: bool,
+    /// `best_move` から始まる、お互いに最善と読んだ手順。`with_pv` と `search_moves` でつける
+    pub pv: Vec<Move>,
+}
+
+impl SearchResult {
+    /// 読み切っていれば最終的な石差を返す
+    pub fn disc_difference(&self) -> Option<i32> {
+        self.exact.then_some(self.score / FINAL_SCORE_UNIT)
+    }
+}
+
+/// 置換表を持ち回って探索する。対局中は同じものを使い回すと前の手の読みが生きる
+pub struct Engine {
+    table: TranspositionTable,
+    endgame_empties: usize,
+    evaluator: Arc<dyn Evaluator>,
+    book: Option<Arc<OpeningBook>>,
+    seed: Option<u64>,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Engine::new(TranspositionTable::DEFAULT_MEGABYTES)
+    }
+}
+
+impl Engine {
+    pub const DEFAULT_ENDGAME_EMPTIES: usize = 14;
+
+    /// 置換表の大きさを MB で指定して作る
+    pub fn new(table_megabytes: usize) -> Self {
+        Engine {
+            table: TranspositionTable::new(table_megabytes),
+            endgame_empties: Self::DEFAULT_ENDGAME_EMPTIES,
+            evaluator: Arc::new(StandardEvaluator::default()),
+            book: None,
+            seed: None,
+        }
+    }
+
+    /// 同じ評価の手からどれを選ぶかを `seed` で決める。同じ局面なら毎回同じ手を選ぶ
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// 探索の末端で使う評価関数を差し替える
+    pub fn with_evaluator(mut self, evaluator: impl Evaluator + 'static) -> Self {
+        self.evaluator = Arc::new(evaluator);
+        self
+    }
+
+    /// 定石にある局面では読まずに定石の手を打つ
+    pub fn with_book(mut self, book: OpeningBook) -> Self {
+        self.book = Some(Arc::new(book));
+        self
+    }
+
+    pub fn book(&self) -> Option<&OpeningBook> {
+        self.book.as_deref()
+    }
+
+    /// 空きマスがこの数以下になったら終局まで読み切る。0 なら読み切らない
+    pub fn with_endgame_empties(mut self, empties: usize) -> Self {
+        self.endgame_empties = empties;
+        self
+    }
+
+    pub fn table(&self) -> &TranspositionTable {
+        &self.table
+    }
+
+    /// `result` の評価値を石差の単位にしたもの。読み切っていなければ評価関数から見込んだ値
+    pub fn discs(&self, result: &SearchResult) -> f64 {
+        let scale = if result.exact {
+            FINAL_SCORE_UNIT
+        } else {
+            self.evaluator.disc_scale()
+        };
+        result.score as f64 / scale as f64
+    }
+
+    /// `Osero::best_move` と同じくレベル1は一番多く返せる手、それ以上は `level` 手先まで読む
+    pub fn best_move(&self, osero: &Osero, with: Pattern, level: usize) -> Option<(usize, usize)> {
+        if level == 1 {
+            self.book_move(osero, with).or_else(|| osero.level1(with))
+        } else {
+            self.search(osero, with, level).best_move
+        }
+    }
+
+    fn book_move(&self, osero: &Osero, with: Pattern) -> Option<(usize, usize)> {
+        let book = self.book.as_ref()?;
+        match self.seed {
+            Some(seed) => book.choose(
+                osero,
+                with,
+                &mut StdRng::seed_from_u64(seed ^ osero.hash(with)),
+            ),
+            None => book.choose(osero, with, &mut rand::rng()),
+        }
+    }
+
+    /// 反復深化つきの PVS で `depth` 手先まで読む。読み筋は `with_pv` でつける
+    pub fn search(&self, osero: &Osero, with: Pattern, depth: usize) -> SearchResult {
+        self.book_result(osero, with)
+            .unwrap_or_else(|| self.iterate(osero, with, depth, None))
+    }
+
+    /// 反復深化で `limit` の間だけ読み、時間内に読み終えた一番深い結果を返す
+    pub fn search_timed(&self, osero: &Osero, with: Pattern, limit: Duration) -> SearchResult {
+        let (_, _, empties) = osero.many();
+        self.book_result(osero, with)
+            .unwrap_or_else(|| self.iterate(osero, with, empties, Some(Instant::now() + limit)))
+    }
+
+    /// すべての合法手について、打ったあとの局面を `depth - 1` 手先まで読み、よい順に並べて返す。
+    /// 定石は使わない
+    pub fn search_moves(&self, osero: &Osero, with: Pattern, depth: usize) -> Vec<SearchResult> {
+        self.search_children(osero, with, |child, with| {
+            self.iterate(child, with, depth.saturating_sub(1), None)
+        })
+    }
+
+    /// `search_moves` を `limit` の間だけ読む。時間は合法手で等分する
+    pub fn search_moves_timed(
+        &self,
+        osero: &Osero,
+        with: Pattern,
+        limit: Duration,
+    ) -> Vec<SearchResult> {
+        let (_, _, empties) = osero.many();
+        let count = osero.get_moveable_points(with).len().max(1) as u32;
+        self.search_children(osero, with, |child, with| {
+            self.iterate(child, with, empties, Some(Instant::now() + limit / count))
+        })
+    }
+
+    // 合法手ごとに打ったあとの局面を `search` で読み、打った側から見た値にしてよい順に並べる
+    fn search_children(
+        &self,
+        osero: &Osero,
+        with: Pattern,
+        search: impl Fn(&Osero, Pattern) -> SearchResult,
+    ) -> Vec<SearchResult> {
+        let mut results: Vec<SearchResult> = osero
+            .get_moveable_points(with)
+            .into_iter()
+            .map(|at| {
+                let mut child = *osero;
+                child.put(with, at);
+                let result = search(&child, with.fliped());
+                let result = self.with_pv(&child, with.fliped(), result);
+                SearchResult {
+                    best_move: Some(at),
+                    score: -result.score,
+                    depth: result.depth + 1,
+                    nodes: result.nodes,
+                    exact: result.exact,
+                    pv: [Move::from(at)].into_iter().chain(result.pv).collect(),
+                }
+            })
+            .collect();
+        results.sort_by_key(|result| -result.score);
+        results
+    }
+
+    fn book_result(&self, osero: &Osero, with: Pattern) -> Option<SearchResult> {
+        let at = self.book_move(osero, with)?;
+        Some(SearchResult {
+            best_move: Some(at),
+            score: 0,
+            depth: 0,
+            nodes: 0,
+            exact: false,
+            pv: vec![Move::from(at)],
+        })
+    }
+
+    /// `osero` を読んだ `result` に、置換表に残った最善手をたどって読み筋をつける。
+    /// 読み切れる局面で置換表に手がなければ読み切って求めるので、時間がかかることがある
+    pub fn with_pv(&self, osero: &Osero, with: Pattern, result: SearchResult) -> SearchResult {
+        let mut pv = vec![];
+        let mut osero = *osero;
+        let mut with = with;
+        let mut first = result.best_move;
+        while pv.len() < result.depth.max(1) && !osero.is_finished() {
+            if !osero.is_moveable_with(with) {
+                pv.push(Move::Pass);
+                with = with.fliped();
+                continue;
+            }
+            let (_, _, empties) = osero.many();
+            let at = first
+                .take()
+                .or_else(|| {
+                    let index = self.table.probe(osero.hash(with))?.best_move? as usize;
+                    Some((index % 8, index / 8))
+                })
+                .or_else(|| {
+                    (empties <= self.endgame_empties)
+                        .then(|| osero.solve_endgame(with).best_move)
+                        .flatten()
+                });
+            match at {
+                Some(at) if osero.is_putabele(with, at) => {
+                    pv.push(Move::from(at));
+                    osero.put(with, at);
+                    with = with.fliped();
+                }
+                _ => break,
+            }
+        }
+        SearchResult { pv, ..result }
+    }
+
+    // 空きマスが少なければ読み切り、多ければ反復深化で読む
+    fn iterate(
+        &self,
+        osero: &Osero,
+        with: Pattern,
+        depth: usize,
+        deadline: Option<Instant>,
+    ) -> SearchResult {
+        let (_, _, empties) = osero.many();
+        if empties > self.endgame_empties {
+            return self.deepen(osero, with, depth, deadline);
+        }
+        // 時間内に読み切れないときのために、時間の 1/4 を使って反復深化の結果を用意しておく
+        let fallback = deadline.map(|deadline| {
+            let now = Instant::now();
+            self.deepen(
+                osero,
+                with,
+                depth,
+                Some(now + deadline.saturating_duration_since(now) / 4),
+            )
+        });
+        match osero.solve_endgame_within(with, &Limit::new(deadline)) {
+            Some(solved) => SearchResult {
+                best_move: solved.best_move,
+                score: solved.score * FINAL_SCORE_UNIT,
+                depth: empties,
+                nodes: solved.nodes + fallback.map_or(0, |result| result.nodes),
+                exact: true,
+                pv: vec![],
+            },
+            None => fallback.expect("時間の制限がなければ必ず読み切る"),
+        }
+    }
+
+    fn deepen(
+        &self,
+        osero: &Osero,
+        with: Pattern,
+        depth: usize,
+        deadline: Option<Instant>,
+    ) -> SearchResult {
+        let start = Instant::now();
+        let mut root_moves = osero.get_moveable_points(with);
+        // 同じ評価の手から毎回同じ手を選ばないよう、先に混ぜておく
+        match self.seed {
+            Some(seed) => root_moves.shuffle(&mut StdRng::seed_from_u64(seed ^ osero.hash(with))),
+            None => root_moves.shuffle(&mut rand::rng()),
+        }
+        root_moves.sort_by_key(|at| -POSITION_SCORE[at.1 * 8 + at.0]);
+
+        let mut result = SearchResult {
+            best_move: None,
+            score: 0,
+            depth: 0,
+            nodes: 0,
+            exact: false,
+            pv: vec![],
+        };
+        if root_moves.is_empty() {
+            let (player, opponent) = osero.sides(with);
+            if bitboard::legal_moves(opponent, player) == 0 {
+                return SearchResult {
+                    score: final_score(player, opponent),
+                    exact: true,
+                    ..result
+                };
+            }
+            // パスして相手の番を読む
+            let passed = self.deepen(osero, with.fliped(), depth, deadline);
+            return SearchResult {
+                best_move: None,
+                score: -passed.score,
+                ..passed
+            };
+        }
+
+        for d in 1..=depth.max(1) {
+            // 深さ1は必ず読み終えて、指す手が無くならないようにする
+            let limit = Limit::new(deadline.filter(|_| d > 1));
+            let (index, score, nodes) = self.search_root(osero, with, &root_moves, d, &limit);
+            if limit.is_stopped() {
+                result.nodes += nodes;
+                break;
+            }
+            // 前の反復で一番よかった手を次の反復で最初に読む
+            let best = root_moves.remove(index);
+            root_moves.insert(0, best);
+            result = SearchResult {
+                best_move: Some(best),
+                score,
+                depth: d,
+                nodes: result.nodes + nodes,
+                exact: false,
+                pv: vec![],
+            };
+            // 残り時間が今までにかかった時間より短いなら、次の深さはまず読み終わらない
+            if let Some(deadline) = deadline
+                && Instant::now() + start.elapsed() > deadline
+            {
+                break;
+            }
+        }
+        result
+    }
+
+    fn search_root(
+        &self,
+        osero: &Osero,
+        with: Pattern,
+        moves: &[(usize, usize)],
+        depth: usize,
+        limit: &Limit,
+    ) -> (usize, i32, u64) {
+        let (player, opponent) = osero.sides(with);
+        let hash = osero.hash(with);
+        let child = |at: (usize, usize)| {
+            let put = bit(at);
+            let flips = bitboard::flips(player, opponent, put);
+            let hash = hash ^ KEYS.moved(with, put, flips);
+            (opponent & !flips, player | put | flips, hash)
+        };
+        let searcher = || Searcher {
+            table: &self.table,
+            evaluator: &*self.evaluator,
+            limit,
+            nodes: 0,
+        };
+
+        // 最初の手だけ全幅で読み、残りはその値を基準に並列に null window で読む
+        let mut first = searcher();
+        let (p, o, h) = child(moves[0]);
+        let alpha = -first.negamax(p, o, with.fliped(), h, depth - 1, -INFINITY, INFINITY);
+
+        let rest = moves[1..]
+            .par_iter()
+            .map(|at| {
+                let mut searcher = searcher();
+                let (p, o, h) = child(*at);
+                let mut score =
+                    -searcher.negamax(p, o, with.fliped(), h, depth - 1, -alpha - 1, -alpha);
+                if score > alpha {
+                    score = -searcher.negamax(p, o, with.fliped(), h, depth - 1, -INFINITY, -alpha);
+                }
+                (score, searcher.nodes)
+            })
+            .collect::<Vec<(i32, u64)>>();
+
+        let mut best = (0, alpha);
+        let mut nodes = first.nodes;
+        for (i, (score, n)) in rest.into_iter().enumerate() {
+            nodes += n;
+            if score > best.1 {
+                best = (i + 1, score);
+            }
+        }
+        (best.0, best.1, nodes)
+    }
+}
+
+// `Osero` から直接読むときに使うエンジン。呼ぶたびに置換表を作らないよう、すべての呼び出しで共有する
+static SHARED_ENGINE: LazyLock<Engine> = LazyLock::new(Engine::default);
+
+/// 既定の設定のエンジンを共有して読む手軽な入口。置換表の大きさや評価関数を変えるときや、
+/// 対局の間の読みを生かしたいときは `Engine` を作って持ち回る
+impl Osero {
+    /// 反復深化つきの PVS で `depth` 手先まで読む
+    pub fn search(&self, with: Pattern, depth: usize) -> SearchResult {
+        SHARED_ENGINE.search(self, with, depth)
+    }
+
+    /// `limit` の間だけ考えて最善手を返す
+    pub fn best_move_timed(&self, with: Pattern, limit: Duration) -> Option<(usize, usize)> {
+        SHARED_ENGINE.search_timed(self, with, limit).best_move
+    }
+}
+
+// 時間切れの判定。スレッド間で stop を共有して、誰かが気づいたら全員止める
+pub(crate) struct Limit {
+    deadline: Option<Instant>,
+    stop: AtomicBool,
+}
+
+impl Limit {
+    pub(crate) fn new(deadline: Option<Instant>) -> Self {
+        Limit {
+            deadline,
+            stop: AtomicBool::new(false),
+        }
+    }
+
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn check(&self) -> bool {
+        if let Some(deadline) = self.deadline
+            && Instant::now() >= deadline
+        {
+            self.stop.store(true, Ordering::Relaxed);
+        }
+        self.is_stopped()
+    }
+}
+
+struct Searcher<'a> {
+    table: &'a TranspositionTable,
+    evaluator: &'a dyn Evaluator,
+    limit: &'a Limit,
+    nodes: u64,
+}
+
+impl Searcher<'_> {
+    #[allow(clippy::too_many_arguments)]
+    fn negamax(
+        &mut self,
+        player: u64,
+        opponent: u64,
+        with: Pattern,
+        hash: u64,
+        depth: usize,
+        mut alpha: i32,
+        mut beta: i32,
+    ) -> i32 {
+        self.nodes += 1;
+        if self.nodes & 1023 == 0 && self.limit.check() {
+            return 0;
+        }
+        let moves = bitboard::legal_moves(player, opponent);
+        if moves == 0 {
+            if bitboard::legal_moves(opponent, player) == 0 {
+                return final_score(player, opponent);
+            }
+            // パス
+            let hash = hash ^ KEYS.white_to_move;
+            return -self.negamax(opponent, player, with.fliped(), hash, depth, -beta, -alpha);
+        }
+        if depth == 0 {
+            let osero = Osero::from_sides(with, player, opponent);
+            return self.evaluator.evaluate(&osero, with);
+        }
+
+        let entry = self.table.probe(hash);
+        if let Some(entry) = entry
+            && entry.depth >= depth
+        {
+            match entry.bound {
+                Bound::Exact => return entry.score,
+                Bound::Lower => alpha = alpha.max(entry.score),
+                Bound::Upper => beta = beta.min(entry.score),
+            }
+            if alpha >= beta {
+                return entry.score;
+            }
+        }
+        let original_alpha = alpha;
+
+        // 置換表の最善手を最初に、残りは SQUARE_ORDER の順に試す
+        let hash_move = entry
+            .and_then(|e| e.best_move)
+            .filter(|i| moves & 1 << i != 0);
+        let ordered = hash_move.into_iter().chain(
+            SQUARE_ORDER
+                .iter()
+                .copied()
+                .filter(|&i| moves & 1 << i != 0 && Some(i) != hash_move),
+        );
+
+        let mut best = -INFINITY;
+        let mut best_move = None;
+        for index in ordered {
+            let put = 1 << index;
+            let flips = bitboard::flips(player, opponent, put);
+            let (p, o) = (opponent & !flips, player | put | flips);
+            let h = hash ^ KEYS.moved(with, put, flips);
+            let next = with.fliped();
+
+            let score = if best_move.is_none() {
+                -self.negamax(p, o, next, h, depth - 1, -beta, -alpha)
+            } else {
+                let score = -self.negamax(p, o, next, h, depth - 1, -alpha - 1, -alpha);
+                if score > alpha && score < beta {
+                    -self.negamax(p, o, next, h, depth - 1, -beta, -score)
+                } else {
+                    score
+                }
+            };
+            // 時間切れで途中までしか読めていない値は使わない
+            if self.limit.is_stopped() {
+                return 0;
+            }
+
+            if score > best {
+                best = score;
+                best_move = Some(index);
+                if score > alpha {
+                    alpha = score;
+                    if alpha >= beta {
+                        break;
+                    }
+                }
+            }
+        }
+
+        let bound = if best <= original_alpha {
+            Bound::Upper
+        } else if best >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.table.store(
+            hash,
+            Entry {
+                score: best,
+                depth,
+                bound,
+                best_move,
+            },
+        );
+        best
+    }
+}
+
+fn final_score(player: u64, opponent: u64) -> i32 {
+    endgame::final_difference(player, opponent) * FINAL_SCORE_UNIT
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endgame::tests::{negamax, random_endgame};
+
+    #[test]
+    fn search_solves_endgames_exactly() {
+        for seed in 0..4 {
+            let (osero, with) = random_endgame(seed, 9);
+            let (player, opponent) = osero.sides(with);
+            let result = Engine::new(1).search(&osero, with, 4);
+            assert!(result.exact, "seed {}", seed);
+            assert_eq!(
+                result.disc_difference(),
+                Some(negamax(player, opponent, false)),
+                "seed {}",
+                seed
+            );
+        }
+    }
+
+    #[test]
+    fn with_pv_follows_the_best_move() {
+        let engine = Engine::new(1);
+        let osero = Osero::default();
+        let result = engine.search(&osero, Pattern::Black, 4);
+        assert!(result.pv.is_empty());
+        let result = engine.with_pv(&osero, Pattern::Black, result);
+        assert_eq!(result.pv.first(), Some(&Move::from_at(result.best_move)));
+        assert!(result.pv.len() <= 4);
+    }
+
+    #[test]
+    fn timed_search_stops_in_the_endgame() {
+        let engine = Engine::new(1);
+        for seed in 0..4 {
+            let (osero, with) = random_endgame(seed, 14);
+            let start = Instant::now();
+            let result = engine.search_timed(&osero, with, Duration::from_millis(20));
+            let elapsed = start.elapsed();
+            assert!(
+                elapsed < Duration::from_millis(200),
+                "seed {}: {:?}",
+                seed,
+                elapsed
+            );
+            assert!(result.best_move.is_some() || !osero.is_moveable_with(with));
+        }
+    }
+
+    #[test]
+    fn search_moves_solves_every_move() {
+        let (osero, with) = random_endgame(100, 9);
+        let results = Engine::new(1).search_moves(&osero, with, 4);
+        assert!(!results.is_empty());
+        assert_eq!(results.len(), osero.get_moveable_points(with).len());
+        for result in results {
+            let mut child = osero;
+            child.put(with, result.best_move.unwrap());
+            // 打った側から見た石差。次は相手の番
+            let (player, opponent) = child.sides(with);
+            assert!(result.exact);
+            assert_eq!(
+                result.disc_difference(),
+                Some(-negamax(opponent, player, false))
+            );
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::ops::Range;
+use std::path::Path;
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::endgame::final_difference;
+use crate::patterns::{SHAPES, UNIT, pattern_indices, phase_of};
+use crate::{Engine, Game, Move, PatternEvaluator};
+
+/// 学習用の1局面。手番側から見た盤面と、その対局の最終的な石差
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Sample {
+    pub player: u64,
+    pub opponent: u64,
+    /// 手番側から見た最終的な石差
+    pub result: i32,
+}
+
+impl Sample {
+    fn to_line(self) -> String {
+        format!(
+            "{:016x} {:016x} {}",
+            self.player, self.opponent, self.result
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Sample> {
+        let mut words = line.split_whitespace();
+        let player = u64::from_str_radix(words.next()?, 16).ok()?;
+        let opponent = u64::from_str_radix(words.next()?, 16).ok()?;
+        let result = words.next()?.parse().ok()?;
+        Some(Sample {
+            player,
+            opponent,
+            result,
+        })
+    }
+}
+
+/// 1行に1局面ずつ「手番側の石 相手の石（16進） 石差」で書き出す
+pub fn write_samples(path: impl AsRef<Path>, samples: &[Sample]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for sample in samples {
+        writeln!(writer, "{}", sample.to_line())?;
+    }
+    writer.flush()
+}
+
+pub fn read_samples(path: impl AsRef<Path>) -> io::Result<Vec<Sample>> {
+    let mut samples = vec![];
+    for (i, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let sample = Sample::from_line(&line).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}行目の局面は無効です", i + 1),
+            )
+        })?;
+        samples.push(sample);
+    }
+    Ok(samples)
+}
+
+/// `engine` どうしで `games` 局打って、途中のすべての局面を最終結果つきで集める。
+/// 最初の `random_plies` 手はランダムに打つ。同じ `seed` なら同じ対局になる
+pub fn self_play(
+    engine: impl Fn() -> Engine + Sync,
+    games: usize,
+    level: usize,
+    random_plies: usize,
+    seed: u64,
+) -> Vec<Sample> {
+    play_games(engine, 0..games, seed, |engine, seed| {
+        play_game(engine, level, random_plies, seed)
+    })
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// 番号が `games` の対局を並列に打つ。`play` には `seed` に対局の番号を足した種と、
+/// その種を設定したエンジンを渡す。同じ `seed` なら同じ対局になる
+pub(crate) fn play_games<T: Send>(
+    engine: impl Fn() -> Engine + Sync,
+    games: Range<usize>,
+    seed: u64,
+    play: impl Fn(&Engine, u64) -> T + Sync,
+) -> Vec<T> {
+    games
+        .into_par_iter()
+        .map(|game| {
+            let seed = seed.wrapping_add(game as u64);
+            let engine = engine().with_seed(seed);
+            // 探索の中の並列化で結果が変わらないよう、1局ごとに1スレッドで打つ
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(1)
+                .build()
+                .expect("スレッドを作れません");
+            pool.install(|| play(&engine, seed))
+        })
+        .collect()
+}
+
+fn play_game(engine: &Engine, level: usize, random_plies: usize, seed: u64) -> Vec<Sample> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut game = Game::new();
+    let mut ply = 0;
+    while !game.is_finished() {
+        let (osero, with) = (*game.board(), game.turn());
+        let at = if !osero.is_moveable_with(with) {
+            None
+        } else if ply < random_plies {
+            osero.get_moveable_points(with).choose(&mut rng).copied()
+        } else {
+            engine.search(&osero, with, level).best_move
+        };
+        game.play(Move::from_at(at));
+        ply += usize::from(at.is_some());
+    }
+    samples_from_game(&game)
+}
+
+/// 棋譜のすべての局面を、その対局の最終的な石差つきで取り出す。棋譜から学習データを作るときに使う
+pub fn samples_from_game(game: &Game) -> Vec<Sample> {
+    let last = game.board();
+    game.positions()
+        .into_iter()
+        .map(|(position, with)| {
+            let (player, opponent) = position.sides(with);
+            let (own, other) = last.sides(with);
+            Sample {
+                player,
+                opponent,
+                result: final_difference(own, other),
+            }
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct FitOptions {
+    pub phases: usize,
+    pub epochs: usize,
+    pub learning_rate: f32,
+}
+
+impl Default for FitOptions {
+    fn default() -> Self {
+        FitOptions {
+            phases: PatternEvaluator::DEFAULT_PHASES,
+            epochs: 200,
+            learning_rate: 1.0,
+        }
+    }
+}
+
+/// 局面の最終的な石差を予測するようにパターンの重みを勾配降下法で合わせる。
+/// `report` にはエポックごとの平均二乗誤差（石差の2乗）を渡す。
+/// `options.phases` は 1〜`PatternEvaluator::MAX_PHASES` でなければならない
+pub fn fit(
+    samples: &[Sample],
+    options: FitOptions,
+    mut report: impl FnMut(usize, f32),
+) -> PatternEvaluator {
+    let phases = options.phases;
+    assert!(
+        (1..=PatternEvaluator::MAX_PHASES).contains(&phases),
+        "phase の数 {} は無効です",
+        phases
+    );
+    let zeros = || -> Vec<Vec<Vec<f32>>> {
+        (0..phases)
+            .map(|_| {
+                SHAPES
+                    .iter()
+                    .map(|shape| vec![0.0; 3usize.pow(shape.len as u32)])
+                    .collect()
+            })
+            .collect()
+    };
+
+    // 局面ごとのパターンの番号は毎回同じなので先に求めておく
+    let features: Vec<(usize, Vec<(usize, usize)>)> = samples
+        .iter()
+        .map(|sample| {
+            let empties = (!(sample.player | sample.opponent)).count_ones() as usize;
+            let phase = phase_of(empties, phases);
+            (
+                phase,
+                pattern_indices(sample.player, sample.opponent).collect(),
+            )
+        })
+        .collect();
+    let per_sample = features.first().map_or(1, |(_, f)| f.len()) as f32;
+
+    // 出てくる回数が少ないパターンの重みが暴れないよう、回数で割って更新する
+    let mut counts = zeros();
+    for (phase, indices) in features.iter() {
+        for &(kind, index) in indices {
+            counts[*phase][kind][index] += 1.0;
+        }
+    }
+
+    let mut weights = zeros();
+    for epoch in 0..options.epochs {
+        let mut gradients = zeros();
+        let mut squared_error = 0.0;
+        for ((phase, indices), sample) in features.iter().zip(samples) {
+            let predicted: f32 = indices
+                .iter()
+                .map(|&(kind, index)| weights[*phase][kind][index])
+                .sum();
+            let error = sample.result as f32 - predicted;
+            squared_error += error * error;
+            for &(kind, index) in indices {
+                gradients[*phase][kind][index] += error;
+            }
+        }
+        for ((weights, gradients), counts) in weights.iter_mut().zip(&gradients).zip(&counts) {
+            for ((weights, gradients), counts) in weights.iter_mut().zip(gradients).zip(counts) {
+                for ((weight, gradient), count) in weights.iter_mut().zip(gradients).zip(counts) {
+                    if *count > 0.0 {
+                        *weight += options.learning_rate * gradient / count / per_sample;
+                    }
+                }
+            }
+        }
+        report(epoch + 1, squared_error / samples.len().max(1) as f32);
+    }
+
+    let quantized = weights
+        .into_iter()
+        .map(|phase| {
+            phase
+                .into_iter()
+                .map(|table| {
+                    table
+                        .into_iter()
+                        .map(|w| {
+                            (w * UNIT as f32)
+                                .round()
+                                .clamp(i16::MIN as f32, i16::MAX as f32)
+                                as i16
+                        })
+                        .collect()
+                })
+                .collect()
+        })
+        .collect();
+    PatternEvaluator::from_weights(quantized)
+}
@@ -0,0 +1,177 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Bound {
+    Exact,
+    // 評価値は本当の値以上
+    Lower,
+    // 評価値は本当の値以下
+    Upper,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct Entry {
+    pub(crate) score: i32,
+    pub(crate) depth: usize,
+    pub(crate) bound: Bound,
+    // 最善手のマスの番号
+    pub(crate) best_move: Option<u32>,
+}
+
+// 1 エントリはキーとデータの AtomicU64 2つ。キーにはデータを xor して入れておき、
+// 別スレッドの書き込みと混ざったエントリは読み出し時に弾く
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+/// 探索スレッド間で共有する置換表
+pub struct TranspositionTable {
+    slots: Vec<Slot>,
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        TranspositionTable::new(TranspositionTable::DEFAULT_MEGABYTES)
+    }
+}
+
+impl TranspositionTable {
+    pub const DEFAULT_MEGABYTES: usize = 16;
+
+    /// `megabytes` MB 以内に収まる大きさで作る
+    pub fn new(megabytes: usize) -> Self {
+        let count = (megabytes * 1024 * 1024 / size_of::<Slot>()).max(1);
+        // 2 のべき乗に切り下げてインデックスをマスクで取れるようにする
+        let count = 1 << count.ilog2();
+        let slots = (0..count)
+            .map(|_| Slot {
+                key: AtomicU64::new(0),
+                data: AtomicU64::new(0),
+            })
+            .collect();
+        TranspositionTable { slots }
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    pub fn clear(&self) {
+        for slot in self.slots.iter() {
+            slot.key.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn slot(&self, hash: u64) -> &Slot {
+        &self.slots[hash as usize & (self.slots.len() - 1)]
+    }
+
+    pub(crate) fn probe(&self, hash: u64) -> Option<Entry> {
+        let slot = self.slot(hash);
+        let data = slot.data.load(Ordering::Relaxed);
+        let key = slot.key.load(Ordering::Relaxed);
+        if data == 0 || key ^ data != hash {
+            return None;
+        }
+        Some(unpack(data))
+    }
+
+    pub(crate) fn store(&self, hash: u64, entry: Entry) {
+        let slot = self.slot(hash);
+        // 同じ局面をより深く読んだ結果があるなら残す
+        if let Some(old) = self.probe(hash)
+            && old.depth > entry.depth
+        {
+            return;
+        }
+        let data = pack(entry);
+        slot.key.store(hash ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+}
+
+// score: 0..32, depth: 32..40, bound: 40..42, best_move: 48..55 (64 は無し), 56 ビット目は使用中の印
+fn pack(entry: Entry) -> u64 {
+    let bound = match entry.bound {
+        Bound::Exact => 0,
+        Bound::Lower => 1,
+        Bound::Upper => 2,
+    };
+    (entry.score as u32 as u64)
+        | (entry.depth.min(u8::MAX as usize) as u64) << 32
+        | bound << 40
+        | (entry.best_move.unwrap_or(64) as u64) << 48
+        | 1 << 56
+}
+
+fn unpack(data: u64) -> Entry {
+    let bound = match (data >> 40) & 0b11 {
+        0 => Bound::Exact,
+        1 => Bound::Lower,
+        _ => Bound::Upper,
+    };
+    let best_move = ((data >> 48) & 0x7f) as u32;
+    Entry {
+        score: data as u32 as i32,
+        depth: ((data >> 32) & 0xff) as usize,
+        bound,
+        best_move: (best_move < 64).then_some(best_move),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(score: i32, depth: usize, bound: Bound, best_move: Option<u32>) -> Entry {
+        Entry {
+            score,
+            depth,
+            bound,
+            best_move,
+        }
+    }
+
+    #[test]
+    fn pack_round_trips() {
+        let entries = [
+            entry(0, 0, Bound::Exact, None),
+            entry(-64_000, 14, Bound::Upper, Some(0)),
+            entry(1_000_000, 60, Bound::Lower, Some(63)),
+            entry(-1_000_000, 1, Bound::Exact, Some(37)),
+        ];
+        for e in entries {
+            assert_eq!(unpack(pack(e)), e);
+            assert_ne!(pack(e), 0);
+        }
+        // 深さは 255 で頭打ちにする
+        assert_eq!(unpack(pack(entry(5, 300, Bound::Exact, None))).depth, 255);
+    }
+
+    #[test]
+    fn keeps_deeper_entries() {
+        let table = TranspositionTable::new(1);
+        let hash = 0x1234_5678_9abc_def0;
+        assert_eq!(table.probe(hash), None);
+        table.store(hash, entry(-30, 6, Bound::Lower, Some(19)));
+        table.store(hash, entry(10, 3, Bound::Exact, None));
+        assert_eq!(
+            table.probe(hash),
+            Some(entry(-30, 6, Bound::Lower, Some(19)))
+        );
+        table.store(hash, entry(10, 8, Bound::Exact, None));
+        assert_eq!(table.probe(hash), Some(entry(10, 8, Bound::Exact, None)));
+
+        // 同じスロットに入る別の局面とは区別する
+        let other = hash ^ (table.len() as u64) << 1;
+        assert_eq!(table.probe(other), None);
+        table.clear();
+        assert_eq!(table.probe(hash), None);
+    }
+}
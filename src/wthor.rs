@@ -0,0 +1,273 @@
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+use crate::{Game, Move, ParseGameError, Record};
+
+const HEADER_LEN: usize = 16;
+const GAME_LEN: usize = 68;
+const PLAYER_LEN: usize = 20;
+const TOURNAMENT_LEN: usize = 26;
+
+/// WTHOR のファイルの先頭16バイト
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct WthorHeader {
+    /// ファイルを作った日 (年, 月, 日)
+    pub created: (u16, u8, u8),
+    /// .wtb なら対局の数
+    pub games: u32,
+    /// .jou・.trn なら名前の数
+    pub records: u16,
+    /// 対局が行われた年
+    pub year: u16,
+    /// 盤の大きさ。0 か 8 なら 8x8
+    pub board_size: u8,
+    /// 理論スコアを求めたときの読みの深さ
+    pub depth: u8,
+}
+
+impl WthorHeader {
+    fn parse(bytes: &[u8; HEADER_LEN]) -> Self {
+        let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+        WthorHeader {
+            created: (bytes[0] as u16 * 100 + bytes[1] as u16, bytes[2], bytes[3]),
+            games: u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]),
+            records: u16_at(8),
+            year: u16_at(10),
+            board_size: bytes[12],
+            depth: bytes[14],
+        }
+    }
+}
+
+/// .wtb の1局。手順は読み込むときに合法かを確かめてある
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct WthorGame {
+    /// .trn の中の番号
+    pub tournament: u16,
+    /// .jou の中の番号
+    pub black: u16,
+    pub white: u16,
+    /// 終局時の黒の石の数
+    pub black_discs: u8,
+    /// 最善に打ったときの黒の石の数
+    pub theoretical: u8,
+    pub game: Game,
+}
+
+impl WthorGame {
+    fn parse(bytes: &[u8]) -> Result<Self, ParseGameError> {
+        let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+        let mut game = Game::new();
+        for (i, &code) in bytes[8..GAME_LEN].iter().enumerate() {
+            // 0 より後には手がない
+            if code == 0 {
+                break;
+            }
+            let (x, y) = (code % 10, code / 10);
+            if !(1..=8).contains(&x) || !(1..=8).contains(&y) {
+                return Err(ParseGameError::Format(format!(
+                    "{}手目の {} はマスではありません",
+                    i + 1,
+                    code
+                )));
+            }
+            let at = (x as usize - 1, y as usize - 1);
+            game.pass_if_forced();
+            let osero = game.board();
+            if !osero.is_putabele(game.turn(), at) {
+                return Err(ParseGameError::Illegal(i + 1, Move::from(at)));
+            }
+            game.play(Move::from(at));
+        }
+        Ok(WthorGame {
+            tournament: u16_at(0),
+            black: u16_at(2),
+            white: u16_at(4),
+            black_discs: bytes[6],
+            theoretical: bytes[7],
+            game,
+        })
+    }
+
+    /// 名前の表から対局者を引いて棋譜にする。日付は対局の年だけ
+    pub fn to_record(&self, year: u16, players: &[String]) -> Record {
+        let name = |i: u16| {
+            players
+                .get(i as usize)
+                .cloned()
+                .unwrap_or_else(|| format!("#{}", i))
+        };
+        Record {
+            black: name(self.black),
+            white: name(self.white),
+            level: None,
+            date: year.to_string(),
+            game: self.game.clone(),
+        }
+    }
+}
+
+/// .wtb の対局をすべて読む。読めなかった対局は何局目か（0 始まり）と理由を `invalid` に入れる
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct WthorDatabase {
+    pub header: WthorHeader,
+    pub games: Vec<WthorGame>,
+    pub invalid: Vec<(usize, ParseGameError)>,
+}
+
+impl WthorDatabase {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::read_from(&mut BufReader::new(File::open(path)?))
+    }
+
+    pub fn read_from(reader: &mut impl Read) -> io::Result<Self> {
+        let header = read_header(reader)?;
+        if header.board_size != 0 && header.board_size != 8 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{}x{} の盤には対応していません",
+                    header.board_size, header.board_size
+                ),
+            ));
+        }
+        let mut games = vec![];
+        let mut invalid = vec![];
+        let mut bytes = [0; GAME_LEN];
+        for i in 0..header.games as usize {
+            reader.read_exact(&mut bytes)?;
+            match WthorGame::parse(&bytes) {
+                Ok(game) => games.push(game),
+                Err(e) => invalid.push((i, e)),
+            }
+        }
+        Ok(WthorDatabase {
+            header,
+            games,
+            invalid,
+        })
+    }
+}
+
+fn read_header(reader: &mut impl Read) -> io::Result<WthorHeader> {
+    let mut bytes = [0; HEADER_LEN];
+    reader.read_exact(&mut bytes)?;
+    Ok(WthorHeader::parse(&bytes))
+}
+
+/// .jou（対局者）か .trn（大会）の名前をすべて読む。番号がそのまま添字になる
+pub fn read_wthor_names(path: impl AsRef<Path>) -> io::Result<Vec<String>> {
+    let path = path.as_ref();
+    let len = match path.extension().and_then(|e| e.to_str()) {
+        Some(e) if e.eq_ignore_ascii_case("trn") => TOURNAMENT_LEN,
+        _ => PLAYER_LEN,
+    };
+    let mut reader = BufReader::new(File::open(path)?);
+    let header = read_header(&mut reader)?;
+    let mut names = vec![];
+    let mut bytes = vec![0; len];
+    for _ in 0..header.records {
+        reader.read_exact(&mut bytes)?;
+        // 名前は 0 で埋めた Latin-1
+        let name: String = bytes
+            .iter()
+            .take_while(|&&b| b != 0)
+            .map(|&b| b as char)
+            .collect();
+        names.push(name.trim().to_string());
+    }
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // c4c5e6e3c6d6f2f5d7e2g4g2g1e7e8 のあと白が2回パスする。黒の 64 石勝ち
+    const MOVES: [u8; 17] = [
+        43, 53, 65, 35, 63, 64, 26, 56, 74, 25, 47, 27, 17, 75, 85, 28, 24,
+    ];
+
+    fn header(games: u32, records: u16, board_size: u8) -> Vec<u8> {
+        let mut bytes = vec![20, 24, 1, 2];
+        bytes.extend(games.to_le_bytes());
+        bytes.extend(records.to_le_bytes());
+        bytes.extend(2001u16.to_le_bytes());
+        bytes.extend([board_size, 0, 22, 0]);
+        bytes
+    }
+
+    fn game(moves: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![1, 0, 2, 0, 3, 0, 64, 64];
+        bytes.extend(moves);
+        bytes.resize(GAME_LEN, 0);
+        bytes
+    }
+
+    #[test]
+    fn reads_games() {
+        let mut bytes = header(3, 0, 8);
+        bytes.extend(game(&MOVES));
+        bytes.extend(game(&[11]));
+        bytes.extend(game(&[43, 99]));
+        let database = WthorDatabase::read_from(&mut bytes.as_slice()).unwrap();
+
+        assert_eq!(
+            database.header,
+            WthorHeader {
+                created: (2024, 1, 2),
+                games: 3,
+                records: 0,
+                year: 2001,
+                board_size: 8,
+                depth: 22,
+            }
+        );
+        assert_eq!(database.games.len(), 1);
+        let wthor = &database.games[0];
+        assert_eq!((wthor.tournament, wthor.black, wthor.white), (1, 2, 3));
+        assert_eq!(
+            wthor.game.transcript(),
+            "c4c5e6e3c6d6f2f5d7e2g4g2g1e7e8pah2pad2"
+        );
+        assert_eq!(wthor.game.result(), Some(64));
+
+        assert_eq!(database.invalid.len(), 2);
+        assert_eq!(
+            database.invalid[0],
+            (1, ParseGameError::Illegal(1, Move::Put(0, 0)))
+        );
+        assert!(matches!(
+            database.invalid[1],
+            (2, ParseGameError::Format(_))
+        ));
+
+        let players: Vec<String> = ["A", "B", "C"].map(String::from).to_vec();
+        let record = wthor.to_record(database.header.year, &players);
+        assert_eq!((record.black.as_str(), record.white.as_str()), ("C", "#3"));
+        assert_eq!(record.date, "2001");
+    }
+
+    #[test]
+    fn rejects_other_board_sizes() {
+        let bytes = header(0, 0, 10);
+        let e = WthorDatabase::read_from(&mut bytes.as_slice()).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn reads_tournament_names() {
+        let mut bytes = header(0, 2, 0);
+        for name in ["Open", "Cup"] {
+            let mut record = name.as_bytes().to_vec();
+            record.resize(TOURNAMENT_LEN, 0);
+            bytes.extend(record);
+        }
+        let path = std::env::temp_dir().join(format!("osero-test-{}.trn", std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+        let names = read_wthor_names(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(names.unwrap(), ["Open", "Cup"]);
+    }
+}
@@ -0,0 +1,119 @@
+use std::sync::LazyLock;
+
+use crate::bitboard;
+use crate::{Osero, Pattern};
+
+pub(crate) struct Keys {
+    pub(crate) black: [u64; 64],
+    pub(crate) white: [u64; 64],
+    // 白番のときに混ぜる
+    pub(crate) white_to_move: u64,
+}
+
+// 乱数は毎回同じになるよう固定の種から splitmix64 で作る
+pub(crate) static KEYS: LazyLock<Keys> = LazyLock::new(|| {
+    let mut state: u64 = 0x0123_4567_89ab_cdef;
+    let mut next = || {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    };
+    Keys {
+        black: std::array::from_fn(|_| next()),
+        white: std::array::from_fn(|_| next()),
+        white_to_move: next(),
+    }
+});
+
+impl Keys {
+    pub(crate) fn side(&self, with: Pattern) -> u64 {
+        if with == Pattern::White {
+            self.white_to_move
+        } else {
+            0
+        }
+    }
+
+    /// `with` が `put` に置いて `flips` を返したときのハッシュの差分（手番の交代も含む）
+    pub(crate) fn moved(&self, with: Pattern, put: u64, flips: u64) -> u64 {
+        let own = if with == Pattern::White {
+            &self.white
+        } else {
+            &self.black
+        };
+        let mut diff = own[put.trailing_zeros() as usize] ^ self.white_to_move;
+        let mut bits = flips;
+        while bits != 0 {
+            let i = bits.trailing_zeros() as usize;
+            diff ^= self.black[i] ^ self.white[i];
+            bits &= bits - 1;
+        }
+        diff
+    }
+}
+
+impl Osero {
+    /// 盤面と手番から Zobrist ハッシュを求める
+    pub fn hash(&self, with: Pattern) -> u64 {
+        let keys = &*KEYS;
+        let mut hash = keys.side(with);
+        for (x, y) in bitboard::squares(self.bitboard(Pattern::Black)) {
+            hash ^= keys.black[y * 8 + x];
+        }
+        for (x, y) in bitboard::squares(self.bitboard(Pattern::White)) {
+            hash ^= keys.white[y * 8 + x];
+        }
+        hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use rand::seq::IndexedRandom;
+
+    use super::*;
+    use crate::bitboard::{bit, flips};
+
+    #[test]
+    fn moved_matches_hash() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut passes = 0;
+        for _ in 0..20 {
+            let mut osero = Osero::default();
+            let mut with = Pattern::Black;
+            let mut hash = osero.hash(with);
+            while !osero.is_finished() {
+                let moves = osero.get_moveable_points(with);
+                match moves.choose(&mut rng) {
+                    Some(&at) => {
+                        let (player, opponent) = osero.sides(with);
+                        let flipped = flips(player, opponent, bit(at));
+                        hash ^= KEYS.moved(with, bit(at), flipped);
+                        osero.put(with, at);
+                    }
+                    None => {
+                        hash ^= KEYS.white_to_move;
+                        passes += 1;
+                    }
+                }
+                with = with.fliped();
+                assert_eq!(hash, osero.hash(with));
+            }
+        }
+        assert!(passes > 0);
+    }
+
+    #[test]
+    fn hash_depends_on_turn() {
+        let osero = Osero::default();
+        assert_ne!(osero.hash(Pattern::Black), osero.hash(Pattern::White));
+        assert_eq!(
+            osero.hash(Pattern::Black) ^ osero.hash(Pattern::White),
+            KEYS.white_to_move
+        );
+    }
+}